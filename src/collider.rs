@@ -1,25 +1,145 @@
 //! This handles collisions, and also supplies a stock of collision shapes that are programmed to be used with the solver
-use crate::vec::{Quat, Vec3};
+use crate::{
+    particle::ALL_CHANNELS,
+    vec::{Quat, Vec3},
+};
 use flexgen::*;
+use std::any::Any;
 
 pub mod capsule;
+pub mod compound;
+pub mod convex;
+pub mod cuboid;
+pub mod heightfield;
 pub mod mesh;
+pub mod meshcache;
+
+/// The transform and flags every collider has, regardless of its shape
+pub struct ColliderState {
+    pub position: Vec3,
+    pub rotation: Quat,
+
+    /// The transform at the start of the current solver tick, used by FleX to interpolate the motion
+    pub prev_position: Vec3,
+    pub prev_rotation: Quat,
+
+    /// Dynamic colliders are expected to move, FleX gives them a lower priority than static ones
+    pub dynamic: bool,
+    /// The collision channel mask, only particle groups sharing a channel with the collider will hit it
+    pub channels: i32,
+    /// Disabled colliders keep their data, but aren't handed to FleX
+    pub enabled: bool,
+}
+
+impl ColliderState {
+    pub fn new(dynamic: bool) -> Self {
+        Self {
+            position: Vec3::new(),
+            rotation: Quat::new(),
+            prev_position: Vec3::new(),
+            prev_rotation: Quat::new(),
+            dynamic,
+            channels: ALL_CHANNELS,
+            enabled: true,
+        }
+    }
+}
+
+/// A single FleX shape, ready to be written to the geometry buffers
+pub struct ShapeTransform {
+    pub position: Vec3,
+    pub rotation: Quat,
+    pub prev_position: Vec3,
+    pub prev_rotation: Quat,
+    pub shapeType: NvFlexCollisionShapeType,
+}
 
 pub trait Collider {
+    /// The transform and flags of the collider
+    fn state(&self) -> &ColliderState;
+    /// Mutable access to the transform and flags of the collider
+    fn state_mut(&mut self) -> &mut ColliderState;
+
     /// The position of the collider
-    fn position(&self) -> Vec3;
+    fn position(&self) -> Vec3 {
+        self.state().position.clone()
+    }
     /// The rotation of the collider
-    fn rotation(&self) -> Quat;
+    fn rotation(&self) -> Quat {
+        self.state().rotation.clone()
+    }
 
     /// The previous position of the collider
-    fn prev_position(&self) -> Vec3;
+    fn prev_position(&self) -> Vec3 {
+        self.state().prev_position.clone()
+    }
     /// The previous rotation of the collider
-    fn prev_rotation(&self) -> Quat;
+    fn prev_rotation(&self) -> Quat {
+        self.state().prev_rotation.clone()
+    }
 
     /// Set the position of the collider
-    fn setPosition(&mut self, pos: Vec3);
+    fn setPosition(&mut self, pos: Vec3) {
+        self.state_mut().position = pos;
+    }
     /// Set the rotation of the collider
-    fn setRotation(&mut self, rot: Quat);
+    fn setRotation(&mut self, rot: Quat) {
+        self.state_mut().rotation = rot;
+    }
+
+    /// Captures the current transform as the previous one, called once per solver tick **(INTERNAL)**
+    fn storePrevious(&mut self) {
+        let state = self.state_mut();
+        state.prev_position = state.position.clone();
+        state.prev_rotation = state.rotation.clone();
+    }
+
+    /// Returns a boolean indicating if the collider moves
+    fn isDynamic(&self) -> bool {
+        self.state().dynamic
+    }
+    /// Marks the collider as moving (or not)
+    fn setDynamic(&mut self, dynamic: bool) {
+        self.state_mut().dynamic = dynamic;
+    }
+
+    /// The collision channel mask of the collider
+    fn channels(&self) -> i32 {
+        self.state().channels
+    }
+    /// Sets the collision channel mask of the collider
+    fn setChannels(&mut self, channels: i32) {
+        self.state_mut().channels = channels & ALL_CHANNELS;
+    }
+
+    /// Returns a boolean indicating if the collider takes part in the simulation
+    fn isEnabled(&self) -> bool {
+        self.state().enabled
+    }
+    /// Adds (or drops) the collider from the simulation, without freeing any of its data
+    fn setEnabled(&mut self, enabled: bool) {
+        self.state_mut().enabled = enabled;
+    }
+
+    /// A short name of the kind of collider, for debugging
+    fn typeName(&self) -> &'static str;
+    /// The lower and upper bounds of the collider, relative to its position and rotation
+    fn bounds(&self) -> (Vec3, Vec3);
+
+    /// How many FleX shapes (and so geometry slots) the collider takes up
+    fn shapeCount(&self) -> usize {
+        1
+    }
+    /// The world transform and type of one of the collider's shapes
+    fn shape(&self, _index: usize) -> ShapeTransform {
+        ShapeTransform {
+            position: self.position(),
+            rotation: self.rotation(),
+            prev_position: self.prev_position(),
+            prev_rotation: self.prev_rotation(),
+            shapeType: self.getShapeFlag(),
+        }
+    }
 
     /// Returns a boolean indicating if the collider has been initialized
     fn isInitialized(&self) -> bool;
@@ -28,8 +148,12 @@ pub trait Collider {
     fn getShapeFlag(&self) -> NvFlexCollisionShapeType;
     /// This function initializes the geometry buffer for the specific `Collider`
     /// This is required because some colliders have special properties, such as a mesh collider
+    /// Colliders with more than one shape fill `shapeCount` slots, starting at `idx`
     unsafe fn initializeGeometry(&mut self, idx: i32, geometryBuffer: *mut NvFlexCollisionGeometry);
 
     /// Lets the collider have access to FleX functions
     fn set_library(&mut self, library: *mut NvFlexLibrary);
+
+    /// Allows downcasting to the concrete collider, for shape-specific operations
+    fn as_any_mut(&mut self) -> &mut dyn Any;
 }
@@ -1,50 +1,42 @@
 //! A capsule collider, this is PERFECT and intended to be used with players
+//!
+//! # Orientation
+//! FleX capsules lie along the X axis, set `upright` to stand the capsule up along the game's up axis (Z)
 
 use crate::{
-    collider::Collider,
+    collider::{Collider, ColliderState},
     vec::{Quat, Vec3},
 };
 
 use flexgen::*;
+use std::any::Any;
 
 pub struct Capsule {
     pub radius: f32,
     pub halfheight: f32,
+    /// Aligns the capsule with the Z axis instead of FleX's X axis
+    pub upright: bool,
 
-    position: Vec3,
-    rotation: Quat,
-
-    prev_position: Vec3,
-    prev_rotation: Quat,
+    state: ColliderState,
 
     initialized: bool,
 }
 
 impl Collider for Capsule {
-    fn position(&self) -> Vec3 {
-        self.position.clone()
+    fn state(&self) -> &ColliderState {
+        &self.state
     }
 
-    fn rotation(&self) -> Quat {
-        self.rotation.clone()
+    fn state_mut(&mut self) -> &mut ColliderState {
+        &mut self.state
     }
 
-    fn prev_position(&self) -> Vec3 {
-        self.prev_position.clone()
+    fn rotation(&self) -> Quat {
+        self.align(&self.state.rotation)
     }
 
     fn prev_rotation(&self) -> Quat {
-        self.prev_rotation.clone()
-    }
-
-    fn setPosition(&mut self, pos: Vec3) {
-        self.prev_position = self.position.clone();
-        self.position = pos;
-    }
-
-    fn setRotation(&mut self, rot: Quat) {
-        self.prev_rotation = self.rotation.clone();
-        self.rotation = rot;
+        self.align(&self.state.prev_rotation)
     }
 
     fn set_library(&mut self, _library: *mut flexgen::NvFlexLibrary) {
@@ -55,6 +47,26 @@ impl Collider for Capsule {
         NvFlexCollisionShapeType_eNvFlexShapeCapsule
     }
 
+    fn typeName(&self) -> &'static str {
+        "capsule"
+    }
+
+    fn bounds(&self) -> (Vec3, Vec3) {
+        let length = self.halfheight + self.radius;
+
+        if self.upright {
+            (
+                Vec3::components(-self.radius, -self.radius, -length),
+                Vec3::components(self.radius, self.radius, length),
+            )
+        } else {
+            (
+                Vec3::components(-length, -self.radius, -self.radius),
+                Vec3::components(length, self.radius, self.radius),
+            )
+        }
+    }
+
     unsafe fn initializeGeometry(
         &mut self,
         idx: i32,
@@ -71,6 +83,10 @@ impl Collider for Capsule {
     fn isInitialized(&self) -> bool {
         self.initialized
     }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
 }
 
 impl Capsule {
@@ -79,11 +95,30 @@ impl Capsule {
         Self {
             radius,
             halfheight,
+            upright: false,
             initialized: false,
-            position: Vec3::new(),
-            rotation: Quat::new(),
-            prev_position: Vec3::new(),
-            prev_rotation: Quat::new(),
+            // Players are pretty much always on the move
+            state: ColliderState::new(true),
+        }
+    }
+
+    /// Changes the dimensions of the capsule, the geometry is re-initialized on the next tick
+    pub fn setSize(&mut self, radius: f32, halfheight: f32) {
+        self.radius = radius;
+        self.halfheight = halfheight;
+        self.initialized = false;
+    }
+
+    /// Applies the upright alignment (if enabled) to a rotation
+    fn align(&self, rotation: &Quat) -> Quat {
+        if !self.upright {
+            return rotation.clone();
         }
+
+        // Rotating -90 degrees around Y maps FleX's X axis onto Z
+        let half = std::f32::consts::FRAC_1_SQRT_2;
+        let alignment = Quat::components(0.0, -half, 0.0, half);
+
+        Quat::quat_mul(&rotation.normalized(), &alignment)
     }
 }
@@ -0,0 +1,187 @@
+//! A compound collider, many child shapes sharing one ID and one transform
+//!
+//! # Children
+//! Each child is a regular `Collider`, its position and rotation are used as the offset from the compound.
+//! Moving the compound moves every child with it, there is no need to move the children from Lua.
+
+use crate::{
+    collider::{Collider, ColliderState, ShapeTransform},
+    vec::{Quat, Vec3},
+};
+
+use flexgen::*;
+use std::any::Any;
+
+pub struct Compound {
+    children: Vec<Box<dyn Collider>>,
+
+    state: ColliderState,
+
+    initialized: bool,
+}
+
+impl Collider for Compound {
+    fn state(&self) -> &ColliderState {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut ColliderState {
+        &mut self.state
+    }
+
+    fn set_library(&mut self, library: *mut NvFlexLibrary) {
+        for child in self.children.iter_mut() {
+            child.set_library(library);
+        }
+    }
+
+    fn getShapeFlag(&self) -> NvFlexCollisionShapeType {
+        // Never used for the geometry, every child reports its own type through `shape`
+        NvFlexCollisionShapeType_eNvFlexShapeBox
+    }
+
+    fn typeName(&self) -> &'static str {
+        "compound"
+    }
+
+    fn bounds(&self) -> (Vec3, Vec3) {
+        if self.children.is_empty() {
+            return (Vec3::new(), Vec3::new());
+        }
+
+        let mut lower = Vec3::components(f32::MAX, f32::MAX, f32::MAX);
+        let mut upper = Vec3::components(f32::MIN, f32::MIN, f32::MIN);
+
+        // Transform the corners of every child's bounds into the compound's space
+        for child in self.children.iter() {
+            let (childLower, childUpper) = child.bounds();
+            let offset = child.position();
+            let rotation = child.rotation().normalized();
+
+            for corner in 0..8 {
+                let local = Vec3::components(
+                    if corner & 1 == 0 {
+                        childLower.x
+                    } else {
+                        childUpper.x
+                    },
+                    if corner & 2 == 0 {
+                        childLower.y
+                    } else {
+                        childUpper.y
+                    },
+                    if corner & 4 == 0 {
+                        childLower.z
+                    } else {
+                        childUpper.z
+                    },
+                );
+                let point = Vec3::add(&offset, &Quat::rotate(&rotation, &local));
+
+                lower = Vec3::components(
+                    lower.x.min(point.x),
+                    lower.y.min(point.y),
+                    lower.z.min(point.z),
+                );
+                upper = Vec3::components(
+                    upper.x.max(point.x),
+                    upper.y.max(point.y),
+                    upper.z.max(point.z),
+                );
+            }
+        }
+
+        (lower, upper)
+    }
+
+    fn shapeCount(&self) -> usize {
+        self.children.iter().map(|child| child.shapeCount()).sum()
+    }
+
+    fn shape(&self, index: usize) -> ShapeTransform {
+        let (child, childIndex) = self.locate(index);
+        let local = child.shape(childIndex);
+
+        let rotation = self.state.rotation.normalized();
+        let prevRotation = self.state.prev_rotation.normalized();
+
+        ShapeTransform {
+            position: Vec3::add(
+                &self.state.position,
+                &Quat::rotate(&rotation, &local.position),
+            ),
+            rotation: Quat::quat_mul(&rotation, &local.rotation),
+            // Children never move relative to the compound, so only the compound's own motion matters
+            prev_position: Vec3::add(
+                &self.state.prev_position,
+                &Quat::rotate(&prevRotation, &local.position),
+            ),
+            prev_rotation: Quat::quat_mul(&prevRotation, &local.rotation),
+            shapeType: local.shapeType,
+        }
+    }
+
+    unsafe fn initializeGeometry(
+        &mut self,
+        idx: i32,
+        geometryBuffer: *mut NvFlexCollisionGeometry,
+    ) {
+        // Children are laid out one after another
+        let mut slot = idx;
+        for child in self.children.iter_mut() {
+            child.initializeGeometry(slot, geometryBuffer);
+            slot += child.shapeCount() as i32;
+        }
+
+        self.initialized = true;
+    }
+
+    fn isInitialized(&self) -> bool {
+        self.initialized && self.children.iter().all(|child| child.isInitialized())
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Compound {
+    pub fn new() -> Self {
+        Self {
+            children: Vec::new(),
+            initialized: false,
+            // Compounds are mostly vehicles and ragdolls, so they move around
+            state: ColliderState::new(true),
+        }
+    }
+
+    /// Adds a child shape at an offset (and rotation) from the compound
+    pub fn addChild(&mut self, mut child: Box<dyn Collider>, offset: Vec3, rotation: Quat) {
+        child.setPosition(offset);
+        child.setRotation(rotation.normalized());
+        child.storePrevious();
+
+        self.children.push(child);
+        // The slots after the new child shift, so everything has to be rewritten
+        self.initialized = false;
+    }
+
+    /// Returns the number of children
+    pub fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    /// Finds which child a shape index belongs to, and the index of the shape inside the child
+    fn locate(&self, mut index: usize) -> (&dyn Collider, usize) {
+        for child in self.children.iter() {
+            let count = child.shapeCount();
+            if index < count {
+                return (child.as_ref(), index);
+            }
+
+            index -= count;
+        }
+
+        panic!("Shape index out of range for compound collider");
+    }
+}
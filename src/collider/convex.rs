@@ -0,0 +1,148 @@
+//! A Convex collider, described by the planes bounding it
+//!
+//! # Planes
+//! Every plane is `(normal, -distance)`, with the normal pointing out of the shape, which is what FleX expects.
+//! A point is inside the shape when it is behind every plane. Brushes from BSP maps map onto this directly.
+use crate::{
+    util::{flex_buffer, flex_map},
+    vec::{Vec3, Vec4},
+};
+use flexgen::*;
+use std::any::Any;
+
+use super::{Collider, ColliderState};
+
+pub struct Convex {
+    state: ColliderState,
+
+    planes: Vec<Vec4>,
+    lower: Vec3,
+    upper: Vec3,
+
+    mesh: Option<NvFlexConvexMeshId>,
+    planesBuffer: *mut NvFlexBuffer,
+
+    lib: *mut NvFlexLibrary,
+    initialized: bool,
+}
+
+impl Collider for Convex {
+    fn state(&self) -> &ColliderState {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut ColliderState {
+        &mut self.state
+    }
+
+    fn getShapeFlag(&self) -> NvFlexCollisionShapeType {
+        NvFlexCollisionShapeType_eNvFlexShapeConvexMesh
+    }
+
+    fn typeName(&self) -> &'static str {
+        "convex"
+    }
+
+    fn bounds(&self) -> (Vec3, Vec3) {
+        (self.lower.clone(), self.upper.clone())
+    }
+
+    fn isInitialized(&self) -> bool {
+        self.initialized
+    }
+
+    fn set_library(&mut self, library: *mut NvFlexLibrary) {
+        self.lib = library;
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    unsafe fn initializeGeometry(
+        &mut self,
+        idx: i32,
+        geometryBuffer: *mut NvFlexCollisionGeometry,
+    ) {
+        // The convex mesh is only created once, moving to another slot just points the new slot at it
+        let mesh = match self.mesh {
+            Some(mesh) => mesh,
+            None => {
+                let planesBuffer = flex_buffer!(self.lib, Vec4, self.planes.len() as i32);
+                let planesPtr: *mut Vec4 = flex_map!(planesBuffer);
+
+                for (i, plane) in self.planes.iter().enumerate() {
+                    *planesPtr.offset(i as isize) = plane.clone();
+                }
+
+                NvFlexUnmap(planesBuffer);
+
+                let mesh = NvFlexCreateConvexMesh(self.lib);
+
+                // The function wants the lower and upper values as a float array
+                let lower_f32: [f32; 3] = [self.lower.x, self.lower.y, self.lower.z];
+                let upper_f32: [f32; 3] = [self.upper.x, self.upper.y, self.upper.z];
+
+                NvFlexUpdateConvexMesh(
+                    self.lib,
+                    mesh,
+                    planesBuffer,
+                    self.planes.len() as i32,
+                    lower_f32.as_ptr(),
+                    upper_f32.as_ptr(),
+                );
+
+                self.planesBuffer = planesBuffer;
+                self.mesh = Some(mesh);
+                mesh
+            }
+        };
+
+        let geometry = geometryBuffer.offset(idx as isize);
+        (*geometry).convexMesh.mesh = mesh;
+        (*geometry).convexMesh.scale[0] = 1.0;
+        (*geometry).convexMesh.scale[1] = 1.0;
+        (*geometry).convexMesh.scale[2] = 1.0;
+
+        self.initialized = true;
+    }
+}
+
+impl Convex {
+    /// Creates a convex shape out of its bounding planes, the bounds have to contain the whole shape
+    pub fn new(planes: Vec<Vec4>, lower: Vec3, upper: Vec3) -> Self {
+        Self {
+            // Convex shapes are usually map brushes, so they start out static
+            state: ColliderState::new(false),
+
+            planes,
+            lower,
+            upper,
+
+            mesh: None,
+            planesBuffer: std::ptr::null_mut(),
+
+            lib: std::ptr::null_mut(),
+            initialized: false,
+        }
+    }
+}
+
+impl Drop for Convex {
+    fn drop(&mut self) {
+        // Never written to the geometry buffers, so there's nothing to free
+        let mesh = match self.mesh {
+            Some(mesh) => mesh,
+            None => return,
+        };
+
+        if self.lib.is_null() {
+            println!("MEMORY LEAK! (Convex): self.lib is a null ptr, cannot free memory");
+        } else {
+            unsafe {
+                NvFlexFreeBuffer(self.planesBuffer);
+                NvFlexDestroyConvexMesh(self.lib, mesh);
+            }
+        }
+    }
+}
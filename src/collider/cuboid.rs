@@ -0,0 +1,79 @@
+//! A box collider, described by its half extents
+
+use crate::{
+    collider::{Collider, ColliderState},
+    vec::Vec3,
+};
+
+use flexgen::*;
+use std::any::Any;
+
+pub struct Cuboid {
+    pub halfExtents: Vec3,
+
+    state: ColliderState,
+
+    initialized: bool,
+}
+
+impl Collider for Cuboid {
+    fn state(&self) -> &ColliderState {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut ColliderState {
+        &mut self.state
+    }
+
+    fn set_library(&mut self, _library: *mut flexgen::NvFlexLibrary) {
+        // Not required for boxes, no independent data is allocated
+    }
+
+    fn getShapeFlag(&self) -> NvFlexCollisionShapeType {
+        NvFlexCollisionShapeType_eNvFlexShapeBox
+    }
+
+    fn typeName(&self) -> &'static str {
+        "box"
+    }
+
+    fn bounds(&self) -> (Vec3, Vec3) {
+        let extents = &self.halfExtents;
+        (
+            Vec3::components(-extents.x, -extents.y, -extents.z),
+            extents.clone(),
+        )
+    }
+
+    unsafe fn initializeGeometry(
+        &mut self,
+        idx: i32,
+        geometryBuffer: *mut NvFlexCollisionGeometry,
+    ) {
+        let geometry = geometryBuffer.offset(idx as isize);
+
+        (*geometry).box_.halfExtents[0] = self.halfExtents.x;
+        (*geometry).box_.halfExtents[1] = self.halfExtents.y;
+        (*geometry).box_.halfExtents[2] = self.halfExtents.z;
+
+        self.initialized = true;
+    }
+
+    fn isInitialized(&self) -> bool {
+        self.initialized
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Cuboid {
+    pub fn new(halfExtents: Vec3) -> Self {
+        Self {
+            halfExtents,
+            initialized: false,
+            state: ColliderState::new(false),
+        }
+    }
+}
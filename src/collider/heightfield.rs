@@ -0,0 +1,216 @@
+//! A Heightfield collider, for terrain and displacements
+//!
+//! # Grid
+//! Heights are stored row by row, `columns` heights per row. Column `c` of row `r` sits at
+//! `(c * spacing, r * spacing, height)` relative to the collider's position, so the position is the corner of the grid.
+//! The grid is triangulated into a FleX triangle mesh the first time the collider is written to the geometry buffers.
+//!
+//! # Updating
+//! `setHeights` rewrites a sub-rectangle of the grid, only the vertices which changed are re-uploaded on the next tick
+use crate::vec::{Vec3, Vec4};
+use flexgen::*;
+use std::any::Any;
+
+use super::{meshcache::SharedMesh, Collider, ColliderState};
+
+pub struct Heightfield {
+    state: ColliderState,
+
+    columns: usize,
+    rows: usize,
+    spacing: f32,
+    heights: Vec<f32>,
+
+    mesh: Option<SharedMesh>,
+    /// The sub-rectangle (first column, first row, last column, last row) changed since the mesh was last uploaded
+    changed: Option<(usize, usize, usize, usize)>,
+
+    lib: *mut NvFlexLibrary,
+    initialized: bool,
+}
+
+impl Collider for Heightfield {
+    fn state(&self) -> &ColliderState {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut ColliderState {
+        &mut self.state
+    }
+
+    fn getShapeFlag(&self) -> NvFlexCollisionShapeType {
+        NvFlexCollisionShapeType_eNvFlexShapeTriangleMesh
+    }
+
+    fn typeName(&self) -> &'static str {
+        "heightfield"
+    }
+
+    fn bounds(&self) -> (Vec3, Vec3) {
+        let (lowest, highest) = self.heightRange();
+        (
+            Vec3::components(0.0, 0.0, lowest),
+            Vec3::components(
+                (self.columns - 1) as f32 * self.spacing,
+                (self.rows - 1) as f32 * self.spacing,
+                highest,
+            ),
+        )
+    }
+
+    fn isInitialized(&self) -> bool {
+        self.initialized
+    }
+
+    fn set_library(&mut self, library: *mut NvFlexLibrary) {
+        self.lib = library;
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    unsafe fn initializeGeometry(
+        &mut self,
+        idx: i32,
+        geometryBuffer: *mut NvFlexCollisionGeometry,
+    ) {
+        let (lower, upper) = self.bounds();
+        let changed = self.changed.take();
+
+        if self.mesh.is_none() {
+            self.mesh = Some(SharedMesh::new(
+                self.lib,
+                &self.vertices(),
+                &self.indices(),
+                lower,
+                upper,
+            ));
+        } else if let Some((firstColumn, firstRow, lastColumn, lastRow)) = changed {
+            let mut changes = Vec::new();
+            for row in firstRow..=lastRow {
+                for column in firstColumn..=lastColumn {
+                    changes.push((row * self.columns + column, self.vertex(column, row)));
+                }
+            }
+
+            self.mesh
+                .as_mut()
+                .unwrap()
+                .updateVertices(&changes, lower, upper);
+        }
+
+        let geometry = geometryBuffer.offset(idx as isize);
+        (*geometry).triMesh.mesh = self.mesh.as_ref().unwrap().id;
+        (*geometry).triMesh.scale[0] = 1.0;
+        (*geometry).triMesh.scale[1] = 1.0;
+        (*geometry).triMesh.scale[2] = 1.0;
+
+        self.initialized = true;
+    }
+}
+
+impl Heightfield {
+    /// Creates a heightfield out of `columns * rows` heights (row by row), `None` if the grid is smaller than 2x2
+    /// or the number of heights doesn't match
+    pub fn new(columns: usize, rows: usize, spacing: f32, heights: Vec<f32>) -> Option<Self> {
+        if columns < 2 || rows < 2 || heights.len() != columns * rows {
+            return None;
+        }
+
+        Some(Self {
+            // Terrain never moves
+            state: ColliderState::new(false),
+
+            columns,
+            rows,
+            spacing,
+            heights,
+
+            mesh: None,
+            changed: None,
+
+            lib: std::ptr::null_mut(),
+            initialized: false,
+        })
+    }
+
+    /// Rewrites the heights of a sub-rectangle `width` columns wide, starting at `column` and `row`
+    /// Returns false (and changes nothing) if the sub-rectangle doesn't fit inside the grid
+    pub fn setHeights(&mut self, column: usize, row: usize, width: usize, heights: &[f32]) -> bool {
+        if width == 0 || heights.is_empty() || heights.len() % width != 0 {
+            return false;
+        }
+
+        let height = heights.len() / width;
+        if column + width > self.columns || row + height > self.rows {
+            return false;
+        }
+
+        for (i, value) in heights.iter().enumerate() {
+            let index = (row + i / width) * self.columns + column + i % width;
+            self.heights[index] = *value;
+        }
+
+        let (lastColumn, lastRow) = (column + width - 1, row + height - 1);
+        self.changed = Some(match self.changed {
+            Some((c0, r0, c1, r1)) => (
+                c0.min(column),
+                r0.min(row),
+                c1.max(lastColumn),
+                r1.max(lastRow),
+            ),
+            None => (column, row, lastColumn, lastRow),
+        });
+
+        // The mesh is only touched on the solver thread, during the next tick
+        self.initialized = false;
+        true
+    }
+
+    /// The lowest and highest height of the grid
+    fn heightRange(&self) -> (f32, f32) {
+        self.heights
+            .iter()
+            .fold((f32::MAX, f32::MIN), |(lowest, highest), height| {
+                (lowest.min(*height), highest.max(*height))
+            })
+    }
+
+    fn vertex(&self, column: usize, row: usize) -> Vec4 {
+        Vec4::components(
+            column as f32 * self.spacing,
+            row as f32 * self.spacing,
+            self.heights[row * self.columns + column],
+            1.0 / 2.0,
+        )
+    }
+
+    fn vertices(&self) -> Vec<Vec4> {
+        let mut vertices = Vec::with_capacity(self.heights.len());
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                vertices.push(self.vertex(column, row));
+            }
+        }
+
+        vertices
+    }
+
+    /// Two triangles per cell, both facing up
+    fn indices(&self) -> Vec<i32> {
+        let mut indices = Vec::with_capacity((self.columns - 1) * (self.rows - 1) * 6);
+        for row in 0..self.rows - 1 {
+            for column in 0..self.columns - 1 {
+                let corner = (row * self.columns + column) as i32;
+                let right = corner + 1;
+                let up = corner + self.columns as i32;
+                let upRight = up + 1;
+
+                indices.extend_from_slice(&[corner, right, upRight, corner, upRight, up]);
+            }
+        }
+
+        indices
+    }
+}
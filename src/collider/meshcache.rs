@@ -0,0 +1,303 @@
+//! A reference-counted cache of FleX triangle meshes
+//!
+//! # Sharing
+//! A `SharedMesh` owns the FleX buffers and the `NvFlexTriangleMeshId`, every `Mesh` collider instance simply holds an `Arc` to one.
+//! The `MeshCache` only keeps a weak reference to each mesh, so the mesh is freed as soon as the last collider using it is removed
+use crate::{
+    util::{flex_buffer, flex_map},
+    vec::{Vec3, Vec4},
+};
+use flexgen::*;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    sync::{Arc, Weak},
+};
+
+/// FleX mesh data which can be used by any number of `Mesh` colliders
+pub struct SharedMesh {
+    pub id: NvFlexTriangleMeshId,
+
+    pub lower: Vec3,
+    pub upper: Vec3,
+
+    verts: *mut NvFlexBuffer,
+    indices: *mut NvFlexBuffer,
+    vertexCount: i32,
+    triangleCount: i32,
+
+    lib: *mut NvFlexLibrary,
+}
+
+impl SharedMesh {
+    /// Uploads the vertices and indices to FleX, creating a new triangle mesh
+    pub unsafe fn new(
+        lib: *mut NvFlexLibrary,
+        vertices: &[Vec4],
+        indices: &[i32],
+        lower: Vec3,
+        upper: Vec3,
+    ) -> Self {
+        // Create the various buffers
+        let verticesBuffer = flex_buffer!(lib, Vec4, vertices.len() as i32);
+        let indicesBuffer = flex_buffer!(lib, i32, indices.len() as i32);
+
+        let verticesPtr: *mut Vec4 = flex_map!(verticesBuffer);
+        let indicesPtr: *mut i32 = flex_map!(indicesBuffer);
+
+        for (i, v) in vertices.iter().enumerate() {
+            *verticesPtr.offset(i as isize) = v.clone();
+        }
+
+        for (i, index) in indices.iter().enumerate() {
+            *indicesPtr.offset(i as isize) = *index;
+        }
+
+        NvFlexUnmap(verticesBuffer);
+        NvFlexUnmap(indicesBuffer);
+
+        let mut mesh = Self {
+            id: NvFlexCreateTriangleMesh(lib),
+            lower,
+            upper,
+            verts: verticesBuffer,
+            indices: indicesBuffer,
+            vertexCount: vertices.len().try_into().unwrap(),
+            triangleCount: (indices.len() / 3).try_into().unwrap(),
+            lib,
+        };
+
+        mesh.upload();
+        mesh
+    }
+
+    /// Hands the buffers over to FleX, which rebuilds the mesh's acceleration structure
+    unsafe fn upload(&mut self) {
+        // The function wants the lower and upper values as a float array
+        let lower_f32: [f32; 3] = [self.lower.x, self.lower.y, self.lower.z];
+        let upper_f32: [f32; 3] = [self.upper.x, self.upper.y, self.upper.z];
+
+        NvFlexUpdateTriangleMesh(
+            self.lib,
+            self.id,
+            self.verts,
+            self.indices,
+            self.vertexCount,
+            self.triangleCount,
+            lower_f32.as_ptr(),
+            upper_f32.as_ptr(),
+        );
+    }
+
+    /// Moves some of the vertices in place (index, new vertex), the triangles stay the same
+    pub unsafe fn updateVertices(&mut self, changes: &[(usize, Vec4)], lower: Vec3, upper: Vec3) {
+        let verticesPtr: *mut Vec4 = flex_map!(self.verts);
+
+        for (index, v) in changes {
+            if *index < self.vertexCount as usize {
+                *verticesPtr.offset(*index as isize) = v.clone();
+            }
+        }
+
+        NvFlexUnmap(self.verts);
+
+        self.lower = lower;
+        self.upper = upper;
+        self.upload();
+    }
+
+    /// Creates a mesh out of a plain triangle list, where every 3 vertices make up a triangle
+    pub unsafe fn from_triangle_list(
+        lib: *mut NvFlexLibrary,
+        vertices: &[Vec4],
+        lower: Vec3,
+        upper: Vec3,
+    ) -> Self {
+        // We have no indices, so they simply count upwards
+        let indices: Vec<i32> = (0..vertices.len() as i32).collect();
+        Self::new(lib, vertices, &indices, lower, upper)
+    }
+
+    /// Computes the lower and upper bounds of a set of vertices
+    pub fn compute_bounds(vertices: &[Vec4]) -> (Vec3, Vec3) {
+        if vertices.is_empty() {
+            return (Vec3::new(), Vec3::new());
+        }
+
+        let mut lower = Vec3::components(f32::MAX, f32::MAX, f32::MAX);
+        let mut upper = Vec3::components(f32::MIN, f32::MIN, f32::MIN);
+
+        for v in vertices {
+            lower = Vec3::components(lower.x.min(v.x), lower.y.min(v.y), lower.z.min(v.z));
+            upper = Vec3::components(upper.x.max(v.x), upper.y.max(v.y), upper.z.max(v.z));
+        }
+
+        (lower, upper)
+    }
+}
+
+impl Drop for SharedMesh {
+    fn drop(&mut self) {
+        if self.lib.is_null() {
+            println!("MEMORY LEAK! (SharedMesh): self.lib is a null ptr, cannot free memory");
+        } else {
+            unsafe {
+                NvFlexFreeBuffer(self.verts);
+                NvFlexFreeBuffer(self.indices);
+                NvFlexDestroyTriangleMesh(self.lib, self.id);
+            }
+
+            println!("Properly cleaned up (SharedMesh)");
+        }
+    }
+}
+
+unsafe impl Send for SharedMesh {}
+unsafe impl Sync for SharedMesh {}
+
+struct MeshEntry {
+    mesh: Weak<SharedMesh>,
+    /// Keeps a freshly registered mesh alive until its first instance is created
+    pending: Option<Arc<SharedMesh>>,
+}
+
+/// A mesh shared by its contents, the vertices are kept to tell apart meshes whose hashes collide
+struct ContentEntry {
+    vertices: Vec<Vec4>,
+    lower: Vec3,
+    upper: Vec3,
+    mesh: Weak<SharedMesh>,
+}
+
+impl ContentEntry {
+    fn matches(&self, vertices: &[Vec4], lower: &Vec3, upper: &Vec3) -> bool {
+        bits(self.lower.x, self.lower.y, self.lower.z) == bits(lower.x, lower.y, lower.z)
+            && bits(self.upper.x, self.upper.y, self.upper.z) == bits(upper.x, upper.y, upper.z)
+            && self.vertices.len() == vertices.len()
+            && self
+                .vertices
+                .iter()
+                .zip(vertices)
+                .all(|(a, b)| bits(a.x, a.y, a.z) == bits(b.x, b.y, b.z))
+    }
+}
+
+/// The exact bits of a point, so meshes are only shared when they are identical
+fn bits(x: f32, y: f32, z: f32) -> [u32; 3] {
+    [x.to_bits(), y.to_bits(), z.to_bits()]
+}
+
+/// Maps a user-supplied name to a `SharedMesh`, and shares meshes with identical contents
+/// Named and content-shared meshes are kept apart, so no name can ever clash with a content hash
+pub struct MeshCache {
+    entries: HashMap<String, MeshEntry>,
+    contents: HashMap<u64, Vec<ContentEntry>>,
+}
+
+impl MeshCache {
+    /// Instantiates a new, empty mesh cache
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            contents: HashMap::new(),
+        }
+    }
+
+    /// Checks if a mesh is registered under the name, and is still alive
+    pub fn contains(&mut self, name: &str) -> bool {
+        self.prune();
+        self.entries.contains_key(name)
+    }
+
+    /// Registers a mesh under the name, the mesh stays alive until the last instance of it is removed
+    /// Returns `false` (and drops the new mesh) if the name is already taken
+    pub fn register(&mut self, name: &str, mesh: SharedMesh) -> bool {
+        if self.contains(name) {
+            return false;
+        }
+
+        let mesh = Arc::new(mesh);
+        self.entries.insert(
+            name.to_string(),
+            MeshEntry {
+                mesh: Arc::downgrade(&mesh),
+                pending: Some(mesh),
+            },
+        );
+
+        true
+    }
+
+    /// Gets a new reference to a registered mesh, to be handed to a `Mesh` collider
+    pub fn instance(&mut self, name: &str) -> Option<Arc<SharedMesh>> {
+        self.prune();
+        let entry = self.entries.get_mut(name)?;
+        // Upgrade before letting go of the pending reference, otherwise the mesh would be freed right here
+        let mesh = entry.mesh.upgrade();
+        entry.pending = None;
+        mesh
+    }
+
+    /// Gets a new reference to the mesh made out of exactly these vertices and bounds, calling `create` if there is none yet
+    pub fn instance_contents(
+        &mut self,
+        vertices: &[Vec4],
+        lower: Vec3,
+        upper: Vec3,
+        create: impl FnOnce() -> SharedMesh,
+    ) -> Arc<SharedMesh> {
+        self.prune();
+        let bucket = self
+            .contents
+            .entry(Self::content_key(vertices, &lower, &upper))
+            .or_default();
+
+        // Equal hashes don't mean equal meshes, so compare the actual data before sharing
+        if let Some(mesh) = bucket
+            .iter()
+            .find(|entry| entry.matches(vertices, &lower, &upper))
+            .and_then(|entry| entry.mesh.upgrade())
+        {
+            return mesh;
+        }
+
+        let mesh = Arc::new(create());
+        bucket.push(ContentEntry {
+            vertices: vertices.to_vec(),
+            lower,
+            upper,
+            mesh: Arc::downgrade(&mesh),
+        });
+
+        mesh
+    }
+
+    /// Forgets every mesh that no longer has any instances
+    fn prune(&mut self) {
+        self.entries
+            .retain(|_, entry| entry.pending.is_some() || entry.mesh.strong_count() > 0);
+
+        self.contents.retain(|_, bucket| {
+            bucket.retain(|entry| entry.mesh.strong_count() > 0);
+            !bucket.is_empty()
+        });
+    }
+
+    /// Drops every pending mesh **(INTERNAL)**, used when shutting down
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.contents.clear();
+    }
+
+    /// Hashes the contents of a triangle list along with its bounds
+    fn content_key(vertices: &[Vec4], lower: &Vec3, upper: &Vec3) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for v in vertices {
+            bits(v.x, v.y, v.z).hash(&mut hasher);
+        }
+        bits(lower.x, lower.y, lower.z).hash(&mut hasher);
+        bits(upper.x, upper.y, upper.z).hash(&mut hasher);
+
+        hasher.finish()
+    }
+}
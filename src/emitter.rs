@@ -0,0 +1,305 @@
+//! Emitters spawn particles on their own every tick, so Lua doesn't have to feed `AddParticles` for a steady pour
+//!
+//! Emitters live in an `EmitterSet` next to the `Scene`, and are handed to Lua by handle just like colliders.
+
+use crate::{
+    particle::Particle,
+    scene::Scene,
+    slotmap::{Handle, SlotMap},
+    vec::{Quat, Vec3},
+};
+use rand::Rng;
+use std::fmt;
+
+/// Errors coming from `EmitterSet` operations, these are raised as Lua errors
+#[derive(Debug)]
+pub enum EmitterError {
+    /// The number isn't an emitter ID at all
+    InvalidId(f64),
+    /// The emitter was removed, the ID will never be valid again
+    StaleId(u64),
+}
+
+impl fmt::Display for EmitterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmitterError::InvalidId(id) => write!(f, "{} is not a valid emitter ID", id),
+            EmitterError::StaleId(id) => {
+                write!(f, "Emitter {} does not exist (was it removed?)", id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EmitterError {}
+
+/// Where around the emitter particles appear
+#[derive(Clone, Debug)]
+pub enum EmitterShape {
+    Point,
+    /// A flat circle facing the direction particles are fired in
+    Disc(f32),
+    /// A box, given by half of its size along each axis
+    Box(Vec3),
+    Sphere(f32),
+}
+
+/// How particles are let out over time
+#[derive(Clone, Debug)]
+pub enum EmitterMode {
+    /// A steady stream, in particles per (simulated) second
+    Continuous(f32),
+    /// `count` particles at once every `interval` simulated seconds, or only once if there is no interval
+    Burst { count: u32, interval: Option<f32> },
+}
+
+#[derive(Clone, Debug)]
+pub struct Emitter {
+    /// Where the emitter is, relative to the parent if it has one
+    pub position: Vec3,
+    /// The velocity particles start with, relative to the parent if it has one
+    pub velocity: Vec3,
+    pub shape: EmitterShape,
+    pub mode: EmitterMode,
+    /// How far (in degrees) particles may stray from `velocity`
+    pub spread: f32,
+    /// The most speed randomly added to each particle, in any direction
+    pub jitter: f32,
+    /// The particle group of the spawned particles
+    pub group: i32,
+    /// The collider the emitter moves along with
+    pub parent: Option<Handle>,
+    pub enabled: bool,
+
+    /// Time (or fractions of particles) left over from the previous ticks
+    accumulator: f32,
+}
+
+impl Emitter {
+    /// Creates an enabled emitter
+    pub fn new(position: Vec3, shape: EmitterShape, mode: EmitterMode) -> Self {
+        Self {
+            position,
+            velocity: Vec3::new(),
+            shape,
+            mode,
+            spread: 0.0,
+            jitter: 0.0,
+            group: 0,
+            parent: None,
+            enabled: true,
+            accumulator: 0.0,
+        }
+    }
+
+    /// Works out how many particles to let out after `dt` more seconds
+    fn due(&mut self, dt: f32) -> u32 {
+        match self.mode {
+            EmitterMode::Continuous(rate) => {
+                self.accumulator += rate.max(0.0) * dt;
+                let count = self.accumulator.floor();
+                self.accumulator -= count;
+                count as u32
+            }
+            EmitterMode::Burst { count, interval } => {
+                // The accumulator counts down to the next burst, so the first one happens right away
+                self.accumulator -= dt;
+                if self.accumulator > 0.0 {
+                    return 0;
+                }
+
+                match interval {
+                    Some(interval) => self.accumulator = (self.accumulator + interval).max(0.0),
+                    None => self.enabled = false,
+                }
+
+                count
+            }
+        }
+    }
+
+    /// Picks a random spot within the shape, around the origin
+    fn samplePosition(&self, rng: &mut impl Rng, forward: &Vec3) -> Vec3 {
+        match &self.shape {
+            EmitterShape::Point => Vec3::new(),
+            EmitterShape::Disc(radius) => {
+                let (side, up) = perpendiculars(forward);
+                let distance = radius * rng.gen::<f32>().sqrt();
+                let angle = rng.gen::<f32>() * std::f32::consts::TAU;
+
+                Vec3::add(
+                    &side.scale(distance * angle.cos()),
+                    &up.scale(distance * angle.sin()),
+                )
+            }
+            EmitterShape::Box(half) => Vec3::components(
+                half.x * rng.gen_range(-1.0..=1.0),
+                half.y * rng.gen_range(-1.0..=1.0),
+                half.z * rng.gen_range(-1.0..=1.0),
+            ),
+            EmitterShape::Sphere(radius) => randomInBall(rng).scale(*radius),
+        }
+    }
+
+    /// Picks a random velocity within the spread cone, then adds the jitter
+    fn sampleVelocity(&self, rng: &mut impl Rng) -> Vec3 {
+        let speed = self.velocity.length();
+        let forward = self.velocity.normalized();
+
+        let mut velocity = self.velocity.clone();
+        if self.spread > 0.0 && speed > f32::EPSILON {
+            // Uniform over the cap of the cone, so particles don't bunch up along the middle
+            let lowest = self.spread.min(180.0).to_radians().cos();
+            let cosine = rng.gen_range(lowest..=1.0);
+            let sine = (1.0 - cosine * cosine).max(0.0).sqrt();
+            let angle = rng.gen::<f32>() * std::f32::consts::TAU;
+
+            let (side, up) = perpendiculars(&forward);
+            velocity = Vec3::add(
+                &forward.scale(cosine),
+                &Vec3::add(
+                    &side.scale(sine * angle.cos()),
+                    &up.scale(sine * angle.sin()),
+                ),
+            )
+            .scale(speed);
+        }
+
+        if self.jitter > 0.0 {
+            velocity = Vec3::add(&velocity, &randomInBall(rng).scale(self.jitter));
+        }
+
+        velocity
+    }
+
+    /// Spawns the particles due after `dt` more seconds, but no more than `limit` of them
+    /// `frame` is the position and rotation of the parent
+    fn emit(
+        &mut self,
+        dt: f32,
+        frame: Option<(Vec3, Quat)>,
+        limit: usize,
+        rng: &mut impl Rng,
+        particles: &mut Vec<Particle>,
+    ) {
+        let count = (self.due(dt) as usize).min(limit);
+        // Discs of emitters without a velocity lie flat, facing up
+        let forward = if self.velocity.length() > f32::EPSILON {
+            self.velocity.normalized()
+        } else {
+            Vec3::components(0.0, 0.0, 1.0)
+        };
+
+        for _ in 0..count {
+            let mut pos = Vec3::add(&self.position, &self.samplePosition(rng, &forward));
+            let mut vel = self.sampleVelocity(rng);
+
+            if let Some((origin, rotation)) = &frame {
+                pos = Vec3::add(origin, &Quat::rotate(rotation, &pos));
+                vel = Quat::rotate(rotation, &vel);
+            }
+
+            particles.push(Particle {
+                pos,
+                vel,
+                group: self.group,
+            });
+        }
+    }
+}
+
+/// Two directions at right angles to `forward` and to each other
+fn perpendiculars(forward: &Vec3) -> (Vec3, Vec3) {
+    // Anything not parallel to forward works as a starting point
+    let helper = if forward.z.abs() < 0.9 {
+        Vec3::components(0.0, 0.0, 1.0)
+    } else {
+        Vec3::components(1.0, 0.0, 0.0)
+    };
+
+    let side = Vec3::cross(forward, &helper).normalized();
+    let up = Vec3::cross(&side, forward);
+    (side, up)
+}
+
+/// A random point inside a ball with a radius of 1
+fn randomInBall(rng: &mut impl Rng) -> Vec3 {
+    loop {
+        let point = Vec3::components(
+            rng.gen_range(-1.0..=1.0),
+            rng.gen_range(-1.0..=1.0),
+            rng.gen_range(-1.0..=1.0),
+        );
+
+        if Vec3::dot(&point, &point) <= 1.0 {
+            return point;
+        }
+    }
+}
+
+/// Every emitter, the solver lets them spawn particles each tick
+pub struct EmitterSet {
+    pub emitters: SlotMap<Emitter>,
+}
+
+impl EmitterSet {
+    /// Creates a new, empty emitter set
+    pub fn new() -> Self {
+        Self {
+            emitters: SlotMap::new(),
+        }
+    }
+
+    /// Adds an emitter, returning its handle
+    pub fn add(&mut self, emitter: Emitter) -> Handle {
+        self.emitters.insert(emitter)
+    }
+
+    /// Gets an emitter by handle
+    pub fn get(&mut self, id: Handle) -> Result<&mut Emitter, EmitterError> {
+        self.emitters
+            .get_mut(id)
+            .ok_or(EmitterError::StaleId(id.to_raw()))
+    }
+
+    /// Removes an emitter by handle
+    pub fn remove(&mut self, id: Handle) -> Result<(), EmitterError> {
+        self.emitters
+            .remove(id)
+            .map(|_| ())
+            .ok_or(EmitterError::StaleId(id.to_raw()))
+    }
+
+    /// Collects the particles every enabled emitter lets out after `dt` more seconds, at most `capacity` of them
+    /// **(INTERNAL)**
+    /// Emitters whose parent collider was removed stay quiet until they get a new parent
+    pub fn emit(&mut self, dt: f32, scene: &Scene, capacity: usize) -> Vec<Particle> {
+        let mut rng = rand::thread_rng();
+        let mut particles = Vec::new();
+
+        for emitter in self.emitters.values_mut() {
+            if !emitter.enabled {
+                continue;
+            }
+
+            // The rotation as it was set, some colliders add an alignment for FleX on top which the emitter
+            // shouldn't follow. Lua can set any quaternion, so it has to be made unit length first
+            let frame = match emitter.parent {
+                Some(parent) => match scene.objects.get(parent) {
+                    Some(record) => Some((
+                        record.collider.position(),
+                        record.collider.state().rotation.normalized(),
+                    )),
+                    None => continue,
+                },
+                None => None,
+            };
+
+            // Whatever doesn't fit is dropped, rather than saved up for a flood once there is room again
+            let limit = capacity - particles.len();
+            emitter.emit(dt, frame, limit, &mut rng, &mut particles);
+        }
+
+        particles
+    }
+}
@@ -2,23 +2,131 @@
 //!
 //! Events allow for lua to manipulate FleX particles and other buffers
 
-use crate::vec::{Vec3, Vec4};
+use crate::{
+    particle::Particle,
+    scene::Scene,
+    slotmap::{Handle, SlotMap},
+    vec::{Vec3, Vec4},
+};
+use schedule::Schedule;
 
+pub mod explosion;
+pub mod impulse;
+pub mod schedule;
 pub mod setparticle;
+pub mod teleport;
+pub mod velocity;
+pub mod vortex;
+
 pub trait Event {
-    /// Allows an event to be invoked with particle data
-    /// Will eventually have more avaliable buffers to manipulate
-    unsafe fn invoke(&self, particles: *mut Vec4, velocities: *mut Vec3, num_particles: i32);
+    /// Allows an event to be invoked with the particle buffers and the scene
+    fn invoke(&mut self, context: &mut EventContext);
+}
+
+/// Everything an event gets to look at (and change) while it is invoked
+///
+/// The slices only cover the particles which currently exist, so `positions[i]`, `velocities[i]` and `phases[i]`
+/// all describe the same particle.
+pub struct EventContext<'a> {
+    /// Where the particles are, along with their inverse mass
+    pub positions: &'a mut [Vec4],
+    /// The velocity of the particles
+    pub velocities: &'a mut [Vec3],
+    /// The phases of the particles, which hold their group and flags
+    pub phases: &'a mut [i32],
+    /// The currently active particles
+    pub actives: &'a [i32],
+    /// Every collider in the scene
+    pub scene: &'a Scene,
+    /// How many times the solver ticked before this one
+    pub tick: u64,
+    /// How much time (in seconds) the solver has simulated so far
+    pub time: f64,
+
+    spawned: Vec<Particle>,
+    killed: Vec<usize>,
+}
+
+impl<'a> EventContext<'a> {
+    /// Creates a new context, the slices have to be the same length
+    pub fn new(
+        positions: &'a mut [Vec4],
+        velocities: &'a mut [Vec3],
+        phases: &'a mut [i32],
+        actives: &'a [i32],
+        scene: &'a Scene,
+        tick: u64,
+        time: f64,
+    ) -> Self {
+        Self {
+            positions,
+            velocities,
+            phases,
+            actives,
+            scene,
+            tick,
+            time,
+            spawned: Vec::new(),
+            killed: Vec::new(),
+        }
+    }
+
+    /// How many particles currently exist
+    pub fn count(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Queues a particle to be spawned once every event was invoked
+    pub fn spawn(&mut self, particle: Particle) {
+        self.spawned.push(particle);
+    }
+
+    /// Marks a particle to be removed once every event was invoked
+    ///
+    /// # Note
+    /// Removing particles moves others into the freed slots, so indices are only meaningful during this tick
+    pub fn kill(&mut self, index: usize) {
+        if index < self.count() {
+            self.killed.push(index);
+        }
+    }
+
+    /// Returns the particles to spawn and the (sorted, unique) indices of the particles to remove
+    pub fn into_changes(self) -> (Vec<Particle>, Vec<usize>) {
+        let mut killed = self.killed;
+        killed.sort_unstable();
+        killed.dedup();
+
+        (self.spawned, killed)
+    }
+}
+
+/// An event which waits for its `Schedule` instead of being invoked on the next tick
+pub struct ScheduledEvent {
+    pub event: Box<dyn Event>,
+    pub schedule: Schedule,
 }
 
 pub struct EventQueue {
     pub events: Vec<Box<dyn Event>>,
+    /// Events invoked later, or over and over again
+    pub scheduled: SlotMap<ScheduledEvent>,
+    /// How many times the solver ticked, advanced by the solver thread
+    pub tick: u64,
+    /// How much time (in seconds) the solver has simulated, advanced by the solver thread
+    /// Kept as a double, a float stops advancing by a whole tick after a few days of simulation
+    pub time: f64,
 }
 
 impl EventQueue {
     /// Instantiates a new event queue
     pub fn new() -> Self {
-        Self { events: Vec::new() }
+        Self {
+            events: Vec::new(),
+            scheduled: SlotMap::new(),
+            tick: 0,
+            time: 0.0,
+        }
     }
 
     /// Adds an event to the queue (consumes the event)
@@ -26,9 +134,65 @@ impl EventQueue {
         self.events.push(event);
     }
 
-    /// Flushes the event queue **(INTERNAL)**
+    /// Schedules an event, returning a handle which can cancel it
+    pub fn schedule(&mut self, event: Box<dyn Event>, schedule: Schedule) -> Handle {
+        self.scheduled.insert(ScheduledEvent { event, schedule })
+    }
+
+    /// Cancels a scheduled event, returns `false` if it already finished (or never existed)
+    pub fn cancel(&mut self, id: Handle) -> bool {
+        self.scheduled.remove(id).is_some()
+    }
+
+    /// Checks if any event has to be invoked on this tick
+    pub fn hasDue(&self) -> bool {
+        !self.events.is_empty()
+            || self
+                .scheduled
+                .values()
+                .iter()
+                .any(|scheduled| scheduled.schedule.isDue(self.tick, self.time))
+    }
+
+    /// Invokes the queued events, then every scheduled event that is due **(INTERNAL)**
+    /// Scheduled events which are done coming back are removed
+    pub fn invoke(&mut self, context: &mut EventContext) {
+        for event in self.events.iter_mut() {
+            event.invoke(context);
+        }
+
+        let mut finished: Vec<Handle> = Vec::new();
+        for (id, scheduled) in self.scheduled.iter_mut() {
+            if !scheduled.schedule.isDue(self.tick, self.time) {
+                continue;
+            }
+
+            scheduled.event.invoke(context);
+            if !scheduled.schedule.advance(self.tick, self.time) {
+                finished.push(id);
+            }
+        }
+
+        for id in finished {
+            self.scheduled.remove(id);
+        }
+    }
+
+    /// Flushes the event queue, and forgets scheduled events which ran out of time **(INTERNAL)**
     pub fn flush(&mut self) {
         self.events.clear();
+
+        // Events whose `end` passed before they were due again would never be removed otherwise
+        let over: Vec<Handle> = self
+            .scheduled
+            .iter()
+            .filter(|(_, scheduled)| scheduled.schedule.isOver(self.tick, self.time))
+            .map(|(id, _)| id)
+            .collect();
+
+        for id in over {
+            self.scheduled.remove(id);
+        }
     }
 }
 
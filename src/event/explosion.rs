@@ -0,0 +1,35 @@
+//! An event to push particles away from a point, weaker the further away they are
+
+use super::{Event, EventContext};
+use crate::vec::Vec3;
+
+pub struct ExplosionEvent {
+    pub center: Vec3,
+    pub radius: f32,
+    /// The speed added to a particle right at the center
+    pub strength: f32,
+    /// How quickly the push fades towards the edge, 1 fades linearly and higher values fade faster
+    pub falloff: f32,
+}
+
+impl Event for ExplosionEvent {
+    fn invoke(&mut self, context: &mut EventContext) {
+        for (particle, velocity) in context.positions.iter().zip(context.velocities.iter_mut()) {
+            let offset = Vec3::sub(&particle.xyz(), &self.center);
+            let distance = offset.length();
+            if distance > self.radius {
+                continue;
+            }
+
+            // Particles sitting right on the center get pushed straight up, rather than nowhere
+            let direction = if distance > f32::EPSILON {
+                offset.scale(1.0 / distance)
+            } else {
+                Vec3::components(0.0, 0.0, 1.0)
+            };
+
+            let strength = self.strength * (1.0 - distance / self.radius).powf(self.falloff);
+            *velocity = Vec3::add(velocity, &direction.scale(strength));
+        }
+    }
+}
@@ -0,0 +1,20 @@
+//! An event to push every particle inside a region in one direction
+
+use super::{Event, EventContext};
+use crate::{region::Region, vec::Vec3};
+
+pub struct ImpulseEvent {
+    pub region: Region,
+    /// The velocity added to every particle in the region
+    pub impulse: Vec3,
+}
+
+impl Event for ImpulseEvent {
+    fn invoke(&mut self, context: &mut EventContext) {
+        for (particle, velocity) in context.positions.iter().zip(context.velocities.iter_mut()) {
+            if self.region.contains(&particle.xyz()) {
+                *velocity = Vec3::add(velocity, &self.impulse);
+            }
+        }
+    }
+}
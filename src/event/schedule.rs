@@ -0,0 +1,74 @@
+//! When a scheduled event is invoked, and for how long it keeps coming back
+
+/// What the numbers in a `Schedule` are measured in
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Clock {
+    /// Solver ticks
+    Ticks,
+    /// Seconds of simulated time
+    Seconds,
+}
+
+#[derive(Clone, Debug)]
+pub struct Schedule {
+    pub clock: Clock,
+    /// When the event is invoked next
+    pub next: f64,
+    /// How long between invocations, `None` invokes the event only once
+    pub period: Option<f64>,
+    /// How many more invocations are left, `None` keeps going forever (or until `end`)
+    pub remaining: Option<u32>,
+    /// Nothing is invoked after this point
+    pub end: Option<f64>,
+}
+
+impl Schedule {
+    /// The current point in time, measured with the clock of the schedule
+    pub fn now(&self, tick: u64, time: f64) -> f64 {
+        match self.clock {
+            Clock::Ticks => tick as f64,
+            Clock::Seconds => time,
+        }
+    }
+
+    /// Checks if the event should be invoked on this tick
+    pub fn isDue(&self, tick: u64, time: f64) -> bool {
+        self.now(tick, time) >= self.next && !self.isOver(tick, time)
+    }
+
+    /// Checks if `end` passed, the event won't be invoked ever again
+    pub fn isOver(&self, tick: u64, time: f64) -> bool {
+        let now = self.now(tick, time);
+        self.end.is_some_and(|end| now > end)
+    }
+
+    /// Moves the schedule past an invocation, returns `false` once the event is never coming back
+    ///
+    /// # Note
+    /// Events are invoked at most once per tick, so periods shorter than a tick are rounded up to one
+    pub fn advance(&mut self, tick: u64, time: f64) -> bool {
+        if let Some(remaining) = &mut self.remaining {
+            *remaining = remaining.saturating_sub(1);
+            if *remaining == 0 {
+                return false;
+            }
+        }
+
+        let period = match self.period {
+            Some(period) if period > 0.0 => period,
+            _ => return false,
+        };
+
+        // Skip whatever was missed, rather than invoking the event several times in one tick to catch up
+        let now = self.now(tick, time);
+        if self.next <= now {
+            let missed = ((now - self.next) / period).floor().max(0.0) + 1.0;
+            self.next += missed * period;
+        }
+
+        match self.end {
+            Some(end) => self.next <= end,
+            None => true,
+        }
+    }
+}
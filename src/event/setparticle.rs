@@ -1,6 +1,6 @@
 //! An event to set the position of every single particle
 
-use super::Event;
+use super::{Event, EventContext};
 use crate::vec::{Vec3, Vec4};
 use rand::Rng;
 
@@ -9,11 +9,13 @@ pub struct SetParticleEvent {
 }
 
 impl Event for SetParticleEvent {
-    unsafe fn invoke(&self, particles: *mut Vec4, velocities: *mut Vec3, num_particles: i32) {
+    fn invoke(&mut self, context: &mut EventContext) {
         let mut rng = rand::thread_rng();
-        for i in 0..num_particles {
-            let mut particle = particles.offset(i as isize);
-            let mut velocity = velocities.offset(i as isize);
+        for (particle, velocity) in context
+            .positions
+            .iter_mut()
+            .zip(context.velocities.iter_mut())
+        {
             let random_velocity: Vec3 = Vec3::components(rng.gen(), rng.gen(), rng.gen());
             *particle = self.position.clone();
             *velocity = random_velocity; // Reset velocity since.. testing proves they get set to the wanted position,
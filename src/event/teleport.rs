@@ -0,0 +1,63 @@
+//! An event to move every particle inside one box into another box
+//!
+//! Particles keep where they were relative to the box, so a particle in the corner of the source box ends up in the
+//! same corner of the destination box, even if the boxes are different sizes. Velocities are left alone.
+
+use super::{Event, EventContext};
+use crate::{region::Region, vec::Vec3};
+
+pub struct TeleportEvent {
+    pub fromLower: Vec3,
+    pub fromUpper: Vec3,
+    pub toLower: Vec3,
+    pub toUpper: Vec3,
+}
+
+impl Event for TeleportEvent {
+    fn invoke(&mut self, context: &mut EventContext) {
+        // Maps one axis of the source box onto the destination box
+        let remap = |value: f32, fromLower: f32, fromUpper: f32, toLower: f32, toUpper: f32| {
+            let size = fromUpper - fromLower;
+            let fraction = if size > f32::EPSILON {
+                (value - fromLower) / size
+            } else {
+                0.5
+            };
+
+            toLower + (toUpper - toLower) * fraction
+        };
+
+        let from = Region::Box {
+            lower: self.fromLower.clone(),
+            upper: self.fromUpper.clone(),
+        };
+
+        for particle in context.positions.iter_mut() {
+            if !from.contains(&particle.xyz()) {
+                continue;
+            }
+
+            particle.x = remap(
+                particle.x,
+                self.fromLower.x,
+                self.fromUpper.x,
+                self.toLower.x,
+                self.toUpper.x,
+            );
+            particle.y = remap(
+                particle.y,
+                self.fromLower.y,
+                self.fromUpper.y,
+                self.toLower.y,
+                self.toUpper.y,
+            );
+            particle.z = remap(
+                particle.z,
+                self.fromLower.z,
+                self.fromUpper.z,
+                self.toLower.z,
+                self.toUpper.z,
+            );
+        }
+    }
+}
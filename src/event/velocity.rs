@@ -0,0 +1,31 @@
+//! An event to set or scale the velocity of every particle inside a region
+
+use super::{Event, EventContext};
+use crate::{region::Region, vec::Vec3};
+
+pub enum VelocityChange {
+    /// Replaces the velocity outright
+    Set(Vec3),
+    /// Multiplies the velocity, 0 stops particles dead and values below 1 slow them down
+    Scale(f32),
+}
+
+pub struct VelocityEvent {
+    pub region: Region,
+    pub change: VelocityChange,
+}
+
+impl Event for VelocityEvent {
+    fn invoke(&mut self, context: &mut EventContext) {
+        for (particle, velocity) in context.positions.iter().zip(context.velocities.iter_mut()) {
+            if !self.region.contains(&particle.xyz()) {
+                continue;
+            }
+
+            *velocity = match &self.change {
+                VelocityChange::Set(value) => value.clone(),
+                VelocityChange::Scale(factor) => velocity.scale(*factor),
+            };
+        }
+    }
+}
@@ -0,0 +1,49 @@
+//! An event to swirl particles around an axis, like water going down a drain
+
+use super::{Event, EventContext};
+use crate::vec::Vec3;
+
+pub struct VortexEvent {
+    pub center: Vec3,
+    /// The direction the particles swirl around, counter-clockwise when looking down it
+    pub axis: Vec3,
+    pub radius: f32,
+    /// The speed added around the axis, fading out towards the radius
+    pub strength: f32,
+    /// The speed added towards the axis, negative values fling particles outwards instead
+    pub pull: f32,
+}
+
+impl Event for VortexEvent {
+    fn invoke(&mut self, context: &mut EventContext) {
+        let axisLength = self.axis.length();
+        if axisLength <= f32::EPSILON {
+            return;
+        }
+        let axis = self.axis.scale(1.0 / axisLength);
+
+        for (particle, velocity) in context.positions.iter().zip(context.velocities.iter_mut()) {
+            let offset = Vec3::sub(&particle.xyz(), &self.center);
+            if offset.length() > self.radius {
+                continue;
+            }
+
+            // Only the part of the offset sideways from the axis matters, the height along it doesn't
+            let radial = Vec3::sub(&offset, &axis.scale(Vec3::dot(&offset, &axis)));
+            let distance = radial.length();
+            if distance <= f32::EPSILON {
+                continue;
+            }
+
+            let outwards = radial.scale(1.0 / distance);
+            let around = Vec3::cross(&axis, &outwards);
+            let fade = 1.0 - distance / self.radius;
+
+            let change = Vec3::sub(
+                &around.scale(self.strength * fade),
+                &outwards.scale(self.pull * fade),
+            );
+            *velocity = Vec3::add(velocity, &change);
+        }
+    }
+}
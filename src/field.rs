@@ -0,0 +1,186 @@
+//! Force fields push on the particles inside them every tick, unlike events which act once
+//!
+//! Fields live in a `FieldSet` next to the `Scene`, and are handed to Lua by handle just like colliders.
+
+pub mod noise;
+
+use crate::{
+    region::{closestOnSegment, Region},
+    slotmap::{Handle, SlotMap},
+    vec::{Vec3, Vec4},
+};
+use std::fmt;
+
+/// Errors coming from `FieldSet` operations, these are raised as Lua errors
+#[derive(Debug)]
+pub enum FieldError {
+    /// The number isn't a force field ID at all
+    InvalidId(f64),
+    /// The force field was removed, the ID will never be valid again
+    StaleId(u64),
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldError::InvalidId(id) => write!(f, "{} is not a valid force field ID", id),
+            FieldError::StaleId(id) => {
+                write!(f, "Force field {} does not exist (was it removed?)", id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FieldError {}
+
+/// How far (in noise cells) noise fields drift through time before starting over
+const NOISE_PERIOD: f64 = 4096.0;
+
+/// Which way a field pushes
+#[derive(Clone, Debug)]
+pub enum FieldKind {
+    /// Always the same direction, like wind or a fan
+    Directional(Vec3),
+    /// Away from the middle of the region (or its line, for capsules), negative strengths attract instead
+    Radial,
+    /// Around an axis through the middle of the region, counter-clockwise when looking down it
+    Vortex(Vec3),
+    /// Curl noise turbulence, `scale` is the size (in units) of a swirl and `speed` how quickly the swirls change
+    Noise { scale: f32, speed: f32 },
+}
+
+/// How the strength of a field fades from its core to its surface
+#[derive(Clone, Copy, Debug)]
+pub enum Falloff {
+    /// Full strength everywhere inside
+    None,
+    Linear,
+    Quadratic,
+    /// Eases out at both ends, so particles don't notice a hard edge
+    Smooth,
+}
+
+impl Falloff {
+    /// The fraction of the strength left at a depth (see `Region::depth`)
+    pub fn scale(self, depth: f32) -> f32 {
+        let left = (1.0 - depth).clamp(0.0, 1.0);
+        match self {
+            Falloff::None => 1.0,
+            Falloff::Linear => left,
+            Falloff::Quadratic => left * left,
+            Falloff::Smooth => left * left * (3.0 - 2.0 * left),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ForceField {
+    pub region: Region,
+    pub kind: FieldKind,
+    /// The acceleration (in units per second squared) at the core of the field
+    pub strength: f32,
+    pub falloff: Falloff,
+    pub enabled: bool,
+}
+
+impl ForceField {
+    /// Creates an enabled field, directions and axes are normalized
+    pub fn new(region: Region, kind: FieldKind, strength: f32, falloff: Falloff) -> Self {
+        let kind = match kind {
+            FieldKind::Directional(direction) => FieldKind::Directional(direction.normalized()),
+            FieldKind::Vortex(axis) => FieldKind::Vortex(axis.normalized()),
+            other => other,
+        };
+
+        Self {
+            region,
+            kind,
+            strength,
+            falloff,
+            enabled: true,
+        }
+    }
+
+    /// The acceleration the field gives a particle at `point`, `None` if the particle is outside
+    pub fn acceleration(&self, point: &Vec3, time: f64) -> Option<Vec3> {
+        let depth = self.region.depth(point)?;
+        let strength = self.strength * self.falloff.scale(depth);
+
+        Some(match &self.kind {
+            FieldKind::Directional(direction) => direction.scale(strength),
+            FieldKind::Radial => {
+                let core = match &self.region {
+                    Region::Capsule { start, end, .. } => closestOnSegment(start, end, point),
+                    region => region.center(),
+                };
+
+                Vec3::sub(point, &core).normalized().scale(strength)
+            }
+            FieldKind::Vortex(axis) => {
+                let offset = Vec3::sub(point, &self.region.center());
+                let radial = Vec3::sub(&offset, &axis.scale(Vec3::dot(&offset, axis)));
+
+                Vec3::cross(axis, &radial.normalized()).scale(strength)
+            }
+            FieldKind::Noise { scale, speed } => {
+                let scale = scale.max(f32::EPSILON);
+                // Wrapped around so the offset stays small enough for a float to move it smoothly
+                let offset = (time * *speed as f64).rem_euclid(NOISE_PERIOD) as f32;
+                let sample =
+                    Vec3::components(point.x / scale, point.y / scale, point.z / scale + offset);
+
+                noise::curl(&sample).scale(strength)
+            }
+        })
+    }
+
+    /// Speeds up every particle inside the field, as if it was pushed on for `dt` seconds
+    pub fn apply(&self, positions: &[Vec4], velocities: &mut [Vec3], dt: f32, time: f64) {
+        for (particle, velocity) in positions.iter().zip(velocities.iter_mut()) {
+            if let Some(acceleration) = self.acceleration(&particle.xyz(), time) {
+                *velocity = Vec3::add(velocity, &acceleration.scale(dt));
+            }
+        }
+    }
+}
+
+/// Every force field, the solver applies them each tick
+pub struct FieldSet {
+    pub fields: SlotMap<ForceField>,
+}
+
+impl FieldSet {
+    /// Creates a new, empty field set
+    pub fn new() -> Self {
+        Self {
+            fields: SlotMap::new(),
+        }
+    }
+
+    /// Adds a field, returning its handle
+    pub fn add(&mut self, field: ForceField) -> Handle {
+        self.fields.insert(field)
+    }
+
+    /// Gets a field by handle
+    pub fn get(&mut self, id: Handle) -> Result<&mut ForceField, FieldError> {
+        self.fields
+            .get_mut(id)
+            .ok_or(FieldError::StaleId(id.to_raw()))
+    }
+
+    /// Removes a field by handle
+    pub fn remove(&mut self, id: Handle) -> Result<(), FieldError> {
+        self.fields
+            .remove(id)
+            .map(|_| ())
+            .ok_or(FieldError::StaleId(id.to_raw()))
+    }
+
+    /// Applies every enabled field to the particles **(INTERNAL)**
+    pub fn apply(&self, positions: &[Vec4], velocities: &mut [Vec3], dt: f32, time: f64) {
+        for field in self.fields.values().iter().filter(|field| field.enabled) {
+            field.apply(positions, velocities, dt, time);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! Smooth random noise, and the curl of it for turbulence
+//!
+//! # Curl noise
+//! Pushing particles along plain noise bunches them up in some places and tears them apart in others. Taking the
+//! curl of three noise fields instead gives a flow which swirls around without any sources or sinks, so the fluid
+//! churns without being squashed.
+use crate::vec::Vec3;
+
+/// How far apart (in noise cells) the samples of the finite differences are
+const DIFFERENCE_STEP: f32 = 1e-2;
+
+/// Scrambles a lattice point into a random looking number
+fn hash(x: i32, y: i32, z: i32) -> u32 {
+    let mut hash = (x as u32).wrapping_mul(0x8da6_b343)
+        ^ (y as u32).wrapping_mul(0xd816_3841)
+        ^ (z as u32).wrapping_mul(0xcb1a_b31f);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0x5bd1_e995);
+    hash ^ (hash >> 15)
+}
+
+/// Dots the offset from a lattice point with one of Perlin's 12 gradient directions
+fn gradient(hash: u32, x: f32, y: f32, z: f32) -> f32 {
+    match hash % 12 {
+        0 => x + y,
+        1 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x + z,
+        5 => -x + z,
+        6 => x - z,
+        7 => -x - z,
+        8 => y + z,
+        9 => -y + z,
+        10 => y - z,
+        _ => -y - z,
+    }
+}
+
+/// Eases a fraction so the noise has no creases at the lattice lines
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(from: f32, to: f32, fraction: f32) -> f32 {
+    from + (to - from) * fraction
+}
+
+/// Gradient noise, roughly between -1 and 1, which changes smoothly over a distance of about 1
+pub fn noise(x: f32, y: f32, z: f32) -> f32 {
+    let (cellX, cellY, cellZ) = (x.floor(), y.floor(), z.floor());
+    let (x, y, z) = (x - cellX, y - cellY, z - cellZ);
+    let (cellX, cellY, cellZ) = (cellX as i32, cellY as i32, cellZ as i32);
+
+    let corner = |dx: i32, dy: i32, dz: i32| {
+        gradient(
+            hash(cellX + dx, cellY + dy, cellZ + dz),
+            x - dx as f32,
+            y - dy as f32,
+            z - dz as f32,
+        )
+    };
+
+    let (u, v, w) = (fade(x), fade(y), fade(z));
+
+    lerp(
+        lerp(
+            lerp(corner(0, 0, 0), corner(1, 0, 0), u),
+            lerp(corner(0, 1, 0), corner(1, 1, 0), u),
+            v,
+        ),
+        lerp(
+            lerp(corner(0, 0, 1), corner(1, 0, 1), u),
+            lerp(corner(0, 1, 1), corner(1, 1, 1), u),
+            v,
+        ),
+        w,
+    )
+}
+
+/// The curl of three unrelated noise fields at a point, see the module docs
+pub fn curl(point: &Vec3) -> Vec3 {
+    // Three potentials, each shifted far away from the others so they don't line up
+    let potential = |axis: usize, x: f32, y: f32, z: f32| {
+        let shift = axis as f32 * 31.416;
+        noise(x + shift, y - shift, z + shift * 0.5)
+    };
+
+    let derivative = |axis: usize, direction: usize| {
+        let mut ahead = [point.x, point.y, point.z];
+        let mut behind = ahead;
+        ahead[direction] += DIFFERENCE_STEP;
+        behind[direction] -= DIFFERENCE_STEP;
+
+        (potential(axis, ahead[0], ahead[1], ahead[2])
+            - potential(axis, behind[0], behind[1], behind[2]))
+            / (2.0 * DIFFERENCE_STEP)
+    };
+
+    Vec3::components(
+        derivative(2, 1) - derivative(1, 2),
+        derivative(0, 2) - derivative(2, 0),
+        derivative(1, 0) - derivative(0, 1),
+    )
+}
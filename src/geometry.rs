@@ -0,0 +1,221 @@
+//! Indexed triangle meshes, and the clean-up done to them before they become `SharedMesh`es
+//!
+//! # Coordinates
+//! Vertices are plain `Vec3`s here, the inverse mass `w` that FleX wants is only added by `flexVertices`
+pub mod simplify;
+pub mod winding;
+
+use crate::vec::{Quat, Vec3, Vec4};
+use std::collections::HashMap;
+
+/// How a mesh is cleaned up before it is uploaded, see `TriangleMesh::prepare`
+pub struct MeshOptions {
+    pub scale: Vec3,
+    pub rotation: Quat,
+    pub offset: Vec3,
+    /// Vertices closer than this are merged, `None` leaves the vertices alone
+    pub weld: Option<f32>,
+    /// Simplifies the mesh down to this many triangles
+    pub targetTriangles: Option<usize>,
+    /// Simplifies the mesh as long as the surface moves less than this (in units)
+    pub tolerance: Option<f32>,
+    /// Makes the triangles agree with each other and face outwards
+    pub repairWinding: bool,
+    /// Makes the triangles face inwards (after repairing them), for tanks and glasses holding fluid
+    pub container: bool,
+    /// Turns every triangle around
+    pub flip: bool,
+    /// Collides from both sides, by adding a turned around copy of every triangle
+    pub doubleSided: bool,
+}
+
+impl Default for MeshOptions {
+    fn default() -> Self {
+        Self {
+            scale: Vec3::components(1.0, 1.0, 1.0),
+            rotation: Quat::identity(),
+            offset: Vec3::new(),
+            weld: None,
+            targetTriangles: None,
+            tolerance: None,
+            repairWinding: false,
+            container: false,
+            flip: false,
+            doubleSided: false,
+        }
+    }
+}
+
+pub struct TriangleMesh {
+    pub vertices: Vec<Vec3>,
+    /// Every 3 indices make up a triangle
+    pub indices: Vec<u32>,
+}
+
+impl TriangleMesh {
+    pub fn new() -> Self {
+        Self {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+        }
+    }
+
+    pub fn triangleCount(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    /// Applies every option, in the order they are listed in `MeshOptions`
+    pub fn prepare(&mut self, options: &MeshOptions) {
+        self.transform(&options.scale, &options.rotation, &options.offset);
+
+        let simplify = options.targetTriangles.is_some() || options.tolerance.is_some();
+        let repair = options.repairWinding || options.container;
+
+        // Edges only connect triangles which share their vertices, so simplifying and repairing need at least
+        // the exact duplicates merged
+        match options.weld {
+            Some(tolerance) => self.weld(tolerance),
+            None if simplify || repair => self.weld(0.0),
+            None => {}
+        }
+
+        if simplify {
+            simplify::simplify(
+                self,
+                options.targetTriangles.unwrap_or(0),
+                options.tolerance.unwrap_or(f32::INFINITY),
+            );
+        }
+
+        if repair {
+            winding::repair(self);
+        }
+
+        // A container is an outward facing mesh turned inside out, a flip on top of that turns it back
+        if options.container != options.flip {
+            self.flipWinding();
+        }
+
+        if options.doubleSided {
+            winding::doubleSided(self);
+        }
+    }
+
+    /// Scales, then rotates, then moves every vertex
+    pub fn transform(&mut self, scale: &Vec3, rotation: &Quat, offset: &Vec3) {
+        let rotation = rotation.normalized();
+
+        for vertex in self.vertices.iter_mut() {
+            let scaled =
+                Vec3::components(vertex.x * scale.x, vertex.y * scale.y, vertex.z * scale.z);
+            *vertex = Vec3::add(offset, &Quat::rotate(&rotation, &scaled));
+        }
+
+        // Mirroring along an odd number of axes turns every triangle inside out
+        if scale.x * scale.y * scale.z < 0.0 {
+            self.flipWinding();
+        }
+    }
+
+    /// Merges vertices closer than `tolerance` to each other, a tolerance of 0 only merges exact duplicates
+    pub fn weld(&mut self, tolerance: f32) {
+        let cellSize = tolerance.max(f32::EPSILON);
+        let cell = |v: &Vec3| {
+            (
+                (v.x / cellSize).floor() as i64,
+                (v.y / cellSize).floor() as i64,
+                (v.z / cellSize).floor() as i64,
+            )
+        };
+
+        let mut grid: HashMap<(i64, i64, i64), Vec<u32>> = HashMap::new();
+        let mut welded: Vec<Vec3> = Vec::new();
+        let mut remap: Vec<u32> = Vec::with_capacity(self.vertices.len());
+
+        for vertex in self.vertices.iter() {
+            let (cx, cy, cz) = cell(vertex);
+
+            // A vertex within the tolerance can only be in this cell or one of its neighbours
+            let mut found = None;
+            'search: for dx in -1..=1 {
+                for dy in -1..=1 {
+                    for dz in -1..=1 {
+                        if let Some(candidates) = grid.get(&(cx + dx, cy + dy, cz + dz)) {
+                            for candidate in candidates {
+                                let other = &welded[*candidate as usize];
+                                if Vec3::sub(other, vertex).length() <= tolerance {
+                                    found = Some(*candidate);
+                                    break 'search;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            let index = found.unwrap_or_else(|| {
+                let index = welded.len() as u32;
+                welded.push(vertex.clone());
+                grid.entry((cx, cy, cz)).or_default().push(index);
+                index
+            });
+            remap.push(index);
+        }
+
+        for index in self.indices.iter_mut() {
+            *index = remap[*index as usize];
+        }
+        self.vertices = welded;
+
+        self.removeDegenerate();
+    }
+
+    /// Drops triangles which use the same vertex more than once, they have no area to collide with
+    pub fn removeDegenerate(&mut self) {
+        let mut kept = Vec::with_capacity(self.indices.len());
+        for triangle in self.indices.chunks_exact(3) {
+            if triangle[0] != triangle[1]
+                && triangle[1] != triangle[2]
+                && triangle[0] != triangle[2]
+            {
+                kept.extend_from_slice(triangle);
+            }
+        }
+
+        self.indices = kept;
+    }
+
+    /// Turns every triangle around, so it faces the other way
+    pub fn flipWinding(&mut self) {
+        for triangle in self.indices.chunks_exact_mut(3) {
+            triangle.swap(1, 2);
+        }
+    }
+
+    /// The lower and upper bounds of every vertex
+    pub fn bounds(&self) -> (Vec3, Vec3) {
+        if self.vertices.is_empty() {
+            return (Vec3::new(), Vec3::new());
+        }
+
+        let mut lower = Vec3::components(f32::MAX, f32::MAX, f32::MAX);
+        let mut upper = Vec3::components(f32::MIN, f32::MIN, f32::MIN);
+
+        for v in self.vertices.iter() {
+            lower = Vec3::components(lower.x.min(v.x), lower.y.min(v.y), lower.z.min(v.z));
+            upper = Vec3::components(upper.x.max(v.x), upper.y.max(v.y), upper.z.max(v.z));
+        }
+
+        (lower, upper)
+    }
+
+    /// The vertices the way `SharedMesh::new` wants them
+    pub fn flexVertices(&self) -> Vec<Vec4> {
+        self.vertices.iter().map(Vec4::from).collect()
+    }
+
+    /// The indices the way `SharedMesh::new` wants them
+    pub fn flexIndices(&self) -> Vec<i32> {
+        self.indices.iter().map(|index| *index as i32).collect()
+    }
+}
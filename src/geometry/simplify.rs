@@ -0,0 +1,521 @@
+//! Quadric edge-collapse simplification (Garland & Heckbert)
+//!
+//! # Quadrics
+//! Every vertex carries the sum of the planes of the triangles around it. Collapsing an edge moves both ends to the
+//! point closest to all of their planes, and the squared distance to those planes is the cost (error) of the collapse.
+//! The cheapest edge is always collapsed first, until the mesh is small enough or the next collapse costs too much.
+//!
+//! Open edges get an extra plane standing up from them, so the outline of an open mesh stays where it is.
+use super::TriangleMesh;
+use crate::vec::Vec3;
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+/// How much more moving an open edge costs than moving a vertex off a triangle's plane
+const BOUNDARY_WEIGHT: f64 = 10.0;
+/// Collapses which tilt a surrounding triangle further than this (the cosine of the angle) are rejected
+const MIN_NORMAL_DOT: f64 = 0.2;
+
+type Point = [f64; 3];
+
+fn sub(a: &Point, b: &Point) -> Point {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: &Point, b: &Point) -> Point {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn dot(a: &Point, b: &Point) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn normalize(a: &Point) -> Option<Point> {
+    let length = dot(a, a).sqrt();
+    if length <= 1e-12 {
+        return None;
+    }
+
+    Some([a[0] / length, a[1] / length, a[2] / length])
+}
+
+/// A symmetric 4x4 matrix, only the upper triangle is stored
+#[derive(Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    /// The quadric of the plane `ax + by + cz + d = 0`, with `(a, b, c)` unit length
+    fn plane(normal: &Point, d: f64, weight: f64) -> Self {
+        let [a, b, c] = *normal;
+        Self([
+            a * a * weight,
+            a * b * weight,
+            a * c * weight,
+            a * d * weight,
+            b * b * weight,
+            b * c * weight,
+            b * d * weight,
+            c * c * weight,
+            c * d * weight,
+            d * d * weight,
+        ])
+    }
+
+    fn add(&self, other: &Quadric) -> Self {
+        let mut sum = self.0;
+        for (value, added) in sum.iter_mut().zip(other.0.iter()) {
+            *value += added;
+        }
+
+        Self(sum)
+    }
+
+    /// The sum of squared distances from the point to every plane in the quadric
+    fn error(&self, p: &Point) -> f64 {
+        let q = &self.0;
+        let [x, y, z] = *p;
+
+        q[0] * x * x
+            + 2.0 * q[1] * x * y
+            + 2.0 * q[2] * x * z
+            + 2.0 * q[3] * x
+            + q[4] * y * y
+            + 2.0 * q[5] * y * z
+            + 2.0 * q[6] * y
+            + q[7] * z * z
+            + 2.0 * q[8] * z
+            + q[9]
+    }
+
+    /// The point with the smallest error, `None` when there isn't a single one (like on a flat surface)
+    fn optimal(&self) -> Option<Point> {
+        let q = &self.0;
+        let det = q[0] * (q[4] * q[7] - q[5] * q[5]) - q[1] * (q[1] * q[7] - q[5] * q[2])
+            + q[2] * (q[1] * q[5] - q[4] * q[2]);
+
+        if det.abs() < 1e-10 {
+            return None;
+        }
+
+        // Cramer's rule on the 3x3 part, against the negated last column
+        let (bx, by, bz) = (-q[3], -q[6], -q[8]);
+        let x = (bx * (q[4] * q[7] - q[5] * q[5]) - q[1] * (by * q[7] - q[5] * bz)
+            + q[2] * (by * q[5] - q[4] * bz))
+            / det;
+        let y = (q[0] * (by * q[7] - bz * q[5]) - bx * (q[1] * q[7] - q[5] * q[2])
+            + q[2] * (q[1] * bz - by * q[2]))
+            / det;
+        let z = (q[0] * (q[4] * bz - q[5] * by) - q[1] * (q[1] * bz - by * q[2])
+            + bx * (q[1] * q[5] - q[4] * q[2]))
+            / det;
+
+        Some([x, y, z])
+    }
+}
+
+/// A possible collapse of the edge `from` -> `to`, valid while neither vertex changed since it was made
+struct Collapse {
+    cost: f64,
+    to: u32,
+    from: u32,
+    toVersion: u32,
+    fromVersion: u32,
+    position: Point,
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost.total_cmp(&other.cost) == Ordering::Equal
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed, so the heap hands out the cheapest collapse first
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+struct Simplifier {
+    positions: Vec<Point>,
+    quadrics: Vec<Quadric>,
+    versions: Vec<u32>,
+    removed: Vec<bool>,
+
+    faces: Vec<[u32; 3]>,
+    alive: Vec<bool>,
+    /// The faces around each vertex, may still list faces which died since
+    vertexFaces: Vec<Vec<usize>>,
+}
+
+impl Simplifier {
+    fn new(mesh: &TriangleMesh) -> Self {
+        let positions: Vec<Point> = mesh
+            .vertices
+            .iter()
+            .map(|v| [v.x as f64, v.y as f64, v.z as f64])
+            .collect();
+        let faces: Vec<[u32; 3]> = mesh
+            .indices
+            .chunks_exact(3)
+            .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+            .collect();
+
+        let mut quadrics = vec![Quadric::default(); positions.len()];
+        let mut vertexFaces = vec![Vec::new(); positions.len()];
+        let mut edgeUses: HashMap<(u32, u32), (usize, u32)> = HashMap::new();
+
+        for (index, face) in faces.iter().enumerate() {
+            for corner in face {
+                vertexFaces[*corner as usize].push(index);
+            }
+
+            let normal = match Self::faceNormal(&positions, face) {
+                Some(normal) => normal,
+                None => continue,
+            };
+            let plane = Quadric::plane(&normal, -dot(&normal, &positions[face[0] as usize]), 1.0);
+            for corner in face {
+                quadrics[*corner as usize] = quadrics[*corner as usize].add(&plane);
+            }
+
+            for i in 0..3 {
+                let (a, b) = (face[i], face[(i + 1) % 3]);
+                edgeUses.entry((a.min(b), a.max(b))).or_insert((index, 0)).1 += 1;
+            }
+        }
+
+        // Keep open edges in place with a plane standing up from them
+        for ((a, b), (face, uses)) in edgeUses {
+            if uses != 1 {
+                continue;
+            }
+
+            let normal = match Self::faceNormal(&positions, &faces[face]) {
+                Some(normal) => normal,
+                None => continue,
+            };
+            let edge = sub(&positions[b as usize], &positions[a as usize]);
+
+            if let Some(side) = normalize(&cross(&edge, &normal)) {
+                let plane =
+                    Quadric::plane(&side, -dot(&side, &positions[a as usize]), BOUNDARY_WEIGHT);
+                quadrics[a as usize] = quadrics[a as usize].add(&plane);
+                quadrics[b as usize] = quadrics[b as usize].add(&plane);
+            }
+        }
+
+        Self {
+            versions: vec![0; positions.len()],
+            removed: vec![false; positions.len()],
+            alive: vec![true; faces.len()],
+            positions,
+            quadrics,
+            faces,
+            vertexFaces,
+        }
+    }
+
+    fn faceNormal(positions: &[Point], face: &[u32; 3]) -> Option<Point> {
+        let a = &positions[face[0] as usize];
+        let b = &positions[face[1] as usize];
+        let c = &positions[face[2] as usize];
+
+        normalize(&cross(&sub(b, a), &sub(c, a)))
+    }
+
+    fn collapse(&self, to: u32, from: u32) -> Collapse {
+        let quadric = self.quadrics[to as usize].add(&self.quadrics[from as usize]);
+        let a = self.positions[to as usize];
+        let b = self.positions[from as usize];
+        let middle = [
+            (a[0] + b[0]) / 2.0,
+            (a[1] + b[1]) / 2.0,
+            (a[2] + b[2]) / 2.0,
+        ];
+
+        // Fall back to the ends or the middle of the edge when there's no single best point
+        let position = quadric.optimal().unwrap_or_else(|| {
+            [a, b, middle]
+                .into_iter()
+                .min_by(|p, q| quadric.error(p).total_cmp(&quadric.error(q)))
+                .unwrap()
+        });
+
+        Collapse {
+            cost: quadric.error(&position).max(0.0),
+            to,
+            from,
+            toVersion: self.versions[to as usize],
+            fromVersion: self.versions[from as usize],
+            position,
+        }
+    }
+
+    /// Every vertex sharing a live face with the vertex
+    fn neighbours(&self, vertex: u32) -> Vec<u32> {
+        let mut neighbours: Vec<u32> = Vec::new();
+        for face in self.vertexFaces[vertex as usize].iter() {
+            if !self.alive[*face] {
+                continue;
+            }
+
+            for corner in self.faces[*face] {
+                if corner != vertex && !neighbours.contains(&corner) {
+                    neighbours.push(corner);
+                }
+            }
+        }
+
+        neighbours
+    }
+
+    /// Checks that moving both ends of the edge doesn't flip any of the faces which survive the collapse
+    fn keepsOrientation(&self, collapse: &Collapse) -> bool {
+        for vertex in [collapse.to, collapse.from] {
+            for face in self.vertexFaces[vertex as usize].iter() {
+                let corners = self.faces[*face];
+                if !self.alive[*face]
+                    || (corners.contains(&collapse.to) && corners.contains(&collapse.from))
+                {
+                    continue;
+                }
+
+                let before = match Self::faceNormal(&self.positions, &corners) {
+                    Some(normal) => normal,
+                    None => continue,
+                };
+
+                let moved: Vec<Point> = corners
+                    .iter()
+                    .map(|corner| {
+                        if *corner == vertex {
+                            collapse.position
+                        } else {
+                            self.positions[*corner as usize]
+                        }
+                    })
+                    .collect();
+
+                let after = match normalize(&cross(
+                    &sub(&moved[1], &moved[0]),
+                    &sub(&moved[2], &moved[0]),
+                )) {
+                    Some(normal) => normal,
+                    None => return false,
+                };
+
+                if dot(&before, &after) < MIN_NORMAL_DOT {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Collapses `from` into `to`, returns how many faces were removed
+    fn apply(&mut self, collapse: &Collapse) -> usize {
+        let (to, from) = (collapse.to, collapse.from);
+        let mut removedFaces = 0;
+
+        self.positions[to as usize] = collapse.position;
+        self.quadrics[to as usize] = self.quadrics[to as usize].add(&self.quadrics[from as usize]);
+        self.removed[from as usize] = true;
+        self.versions[to as usize] += 1;
+
+        for face in std::mem::take(&mut self.vertexFaces[from as usize]) {
+            if !self.alive[face] {
+                continue;
+            }
+
+            if self.faces[face].contains(&to) {
+                self.alive[face] = false;
+                removedFaces += 1;
+                continue;
+            }
+
+            for corner in self.faces[face].iter_mut() {
+                if *corner == from {
+                    *corner = to;
+                }
+            }
+            self.vertexFaces[to as usize].push(face);
+        }
+
+        let alive = &self.alive;
+        self.vertexFaces[to as usize].retain(|face| alive[*face]);
+
+        removedFaces
+    }
+}
+
+/// Collapses edges until the mesh has at most `targetTriangles` triangles, or the next collapse would move the
+/// surface further than `tolerance` (in units). The mesh must already be welded, or nothing can collapse.
+pub fn simplify(mesh: &mut TriangleMesh, targetTriangles: usize, tolerance: f32) {
+    let maxError = (tolerance as f64) * (tolerance as f64);
+    let mut simplifier = Simplifier::new(mesh);
+    let mut triangles = simplifier.faces.len();
+
+    let mut heap: BinaryHeap<Collapse> = BinaryHeap::new();
+    let mut queued: HashSet<(u32, u32)> = HashSet::new();
+    for face in simplifier.faces.iter() {
+        for i in 0..3 {
+            let (a, b) = (face[i], face[(i + 1) % 3]);
+            // Edges are shared by several faces, which may not agree on its direction, only queue it once
+            let edge = (a.min(b), a.max(b));
+            if queued.insert(edge) {
+                heap.push(simplifier.collapse(edge.0, edge.1));
+            }
+        }
+    }
+
+    while triangles > targetTriangles {
+        let collapse = match heap.pop() {
+            Some(collapse) => collapse,
+            None => break,
+        };
+
+        let (to, from) = (collapse.to as usize, collapse.from as usize);
+        if simplifier.removed[to]
+            || simplifier.removed[from]
+            || simplifier.versions[to] != collapse.toVersion
+            || simplifier.versions[from] != collapse.fromVersion
+        {
+            continue;
+        }
+
+        // Everything left in the heap costs at least as much
+        if collapse.cost > maxError {
+            break;
+        }
+
+        if !simplifier.keepsOrientation(&collapse) {
+            continue;
+        }
+
+        triangles -= simplifier.apply(&collapse);
+
+        for neighbour in simplifier.neighbours(collapse.to) {
+            heap.push(simplifier.collapse(collapse.to, neighbour));
+        }
+    }
+
+    // Only keep the vertices which are still used
+    let mut remap: Vec<Option<u32>> = vec![None; simplifier.positions.len()];
+    let mut vertices: Vec<Vec3> = Vec::new();
+    let mut indices: Vec<u32> = Vec::with_capacity(triangles * 3);
+
+    for (face, alive) in simplifier.faces.iter().zip(simplifier.alive.iter()) {
+        if !alive {
+            continue;
+        }
+
+        for corner in face {
+            let index = *remap[*corner as usize].get_or_insert_with(|| {
+                let p = simplifier.positions[*corner as usize];
+                vertices.push(Vec3::components(p[0] as f32, p[1] as f32, p[2] as f32));
+                (vertices.len() - 1) as u32
+            });
+            indices.push(index);
+        }
+    }
+
+    mesh.vertices = vertices;
+    mesh.indices = indices;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A flat square grid of `size` by `size` cells on the XY plane, facing up
+    fn grid(size: u32) -> TriangleMesh {
+        let mut mesh = TriangleMesh::new();
+        for y in 0..=size {
+            for x in 0..=size {
+                mesh.vertices
+                    .push(Vec3::components(x as f32, y as f32, 0.0));
+            }
+        }
+
+        let row = size + 1;
+        for y in 0..size {
+            for x in 0..size {
+                let corner = y * row + x;
+                mesh.indices.extend_from_slice(&[
+                    corner,
+                    corner + 1,
+                    corner + row + 1,
+                    corner,
+                    corner + row + 1,
+                    corner + row,
+                ]);
+            }
+        }
+
+        mesh
+    }
+
+    /// The total area of the triangles, which must all face up
+    fn upwardArea(mesh: &TriangleMesh) -> f32 {
+        mesh.indices
+            .chunks_exact(3)
+            .map(|triangle| {
+                let corner = |i: usize| &mesh.vertices[triangle[i] as usize];
+                let normal = Vec3::cross(
+                    &Vec3::sub(corner(1), corner(0)),
+                    &Vec3::sub(corner(2), corner(0)),
+                );
+                assert!(normal.z > 0.0, "a triangle was flipped");
+                normal.z / 2.0
+            })
+            .sum()
+    }
+
+    #[test]
+    fn flat_grid_keeps_bounds_and_area() {
+        let mut mesh = grid(8);
+        let (lower, upper) = mesh.bounds();
+
+        simplify(&mut mesh, 2, 0.01);
+
+        // Everything on the inside is flat, so only the outline should be left holding the shape
+        assert!(mesh.triangleCount() < 8 * 8 * 2 / 4);
+
+        let (newLower, newUpper) = mesh.bounds();
+        assert_eq!(
+            (newLower.x, newLower.y, newLower.z),
+            (lower.x, lower.y, lower.z)
+        );
+        assert_eq!(
+            (newUpper.x, newUpper.y, newUpper.z),
+            (upper.x, upper.y, upper.z)
+        );
+        assert!((upwardArea(&mesh) - 64.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn zero_tolerance_keeps_curved_surfaces() {
+        // Lifting the middle vertex makes a tent, no collapse can keep its shape exactly
+        let mut mesh = grid(2);
+        mesh.vertices[4].z = 1.0;
+
+        simplify(&mut mesh, 0, 0.0);
+        assert_eq!(mesh.triangleCount(), 8);
+    }
+}
@@ -0,0 +1,193 @@
+//! Making the triangles of a mesh face a consistent way
+//!
+//! # Repair
+//! Two triangles sharing an edge face the same way when they walk that edge in opposite directions. Starting from any
+//! triangle, its neighbours are turned around until they agree with it, spreading across each connected piece.
+//! Every piece is then turned inside out if needed, so that its triangles face out of the volume it encloses.
+use super::TriangleMesh;
+use crate::vec::Vec3;
+use std::collections::{HashMap, VecDeque};
+
+/// Makes every triangle agree with its neighbours, and face outwards
+pub fn repair(mesh: &mut TriangleMesh) {
+    let faceCount = mesh.triangleCount();
+    let corners = |face: usize| -> [u32; 3] {
+        let start = face * 3;
+        [
+            mesh.indices[start],
+            mesh.indices[start + 1],
+            mesh.indices[start + 2],
+        ]
+    };
+
+    // Every face using each edge, no matter which way around
+    let mut edgeFaces: HashMap<(u32, u32), Vec<usize>> = HashMap::new();
+    for face in 0..faceCount {
+        let [a, b, c] = corners(face);
+        for (from, to) in [(a, b), (b, c), (c, a)] {
+            edgeFaces
+                .entry((from.min(to), from.max(to)))
+                .or_default()
+                .push(face);
+        }
+    }
+
+    // Checks if a face (turned around or not) walks from `from` to `to`
+    let walks = |face: usize, flipped: bool, from: u32, to: u32| -> bool {
+        let [a, b, c] = corners(face);
+        let forward = [(a, b), (b, c), (c, a)].contains(&(from, to));
+        forward != flipped
+    };
+
+    let mut flipped = vec![false; faceCount];
+    let mut visited = vec![false; faceCount];
+
+    for start in 0..faceCount {
+        if visited[start] {
+            continue;
+        }
+
+        visited[start] = true;
+        let mut piece = vec![start];
+        let mut queue = VecDeque::from([start]);
+
+        while let Some(face) = queue.pop_front() {
+            let [a, b, c] = corners(face);
+            let edges = if flipped[face] {
+                [(a, c), (c, b), (b, a)]
+            } else {
+                [(a, b), (b, c), (c, a)]
+            };
+
+            for (from, to) in edges {
+                for neighbour in edgeFaces[&(from.min(to), from.max(to))].iter() {
+                    if visited[*neighbour] {
+                        continue;
+                    }
+
+                    // A neighbour facing the same way walks the shared edge backwards
+                    visited[*neighbour] = true;
+                    flipped[*neighbour] = walks(*neighbour, false, from, to);
+                    piece.push(*neighbour);
+                    queue.push_back(*neighbour);
+                }
+            }
+        }
+
+        // A piece facing outwards encloses a positive volume, measured around its own centre
+        let mut centre = Vec3::new();
+        for face in piece.iter() {
+            for corner in corners(*face) {
+                centre = Vec3::add(&centre, &mesh.vertices[corner as usize]);
+            }
+        }
+        let centre = centre.scale(1.0 / (piece.len() * 3) as f32);
+
+        let volume: f32 = piece
+            .iter()
+            .map(|face| {
+                let [a, b, c] = corners(*face);
+                let (b, c) = if flipped[*face] { (c, b) } else { (b, c) };
+                let (a, b, c) = (
+                    Vec3::sub(&mesh.vertices[a as usize], &centre),
+                    Vec3::sub(&mesh.vertices[b as usize], &centre),
+                    Vec3::sub(&mesh.vertices[c as usize], &centre),
+                );
+                Vec3::dot(&a, &Vec3::cross(&b, &c))
+            })
+            .sum();
+
+        if volume < 0.0 {
+            for face in piece {
+                flipped[face] = !flipped[face];
+            }
+        }
+    }
+
+    for (face, flip) in flipped.iter().enumerate() {
+        if *flip {
+            mesh.indices.swap(face * 3 + 1, face * 3 + 2);
+        }
+    }
+}
+
+/// Adds a turned around copy of every triangle, so the mesh collides from both sides
+pub fn doubleSided(mesh: &mut TriangleMesh) {
+    let back: Vec<u32> = mesh
+        .indices
+        .chunks_exact(3)
+        .flat_map(|triangle| [triangle[0], triangle[2], triangle[1]])
+        .collect();
+
+    mesh.indices.extend(back);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::MeshOptions;
+
+    /// A cube from 0 to `size` on every axis, with every triangle facing outwards
+    fn cube(size: f32) -> TriangleMesh {
+        let mut mesh = TriangleMesh::new();
+        for corner in 0..8 {
+            mesh.vertices.push(Vec3::components(
+                (corner & 1) as f32 * size,
+                ((corner >> 1) & 1) as f32 * size,
+                ((corner >> 2) & 1) as f32 * size,
+            ));
+        }
+
+        mesh.indices = vec![
+            0, 2, 1, 1, 2, 3, // -Z
+            4, 5, 6, 5, 7, 6, // +Z
+            0, 1, 4, 1, 5, 4, // -Y
+            2, 6, 3, 3, 6, 7, // +Y
+            0, 4, 2, 2, 4, 6, // -X
+            1, 3, 5, 3, 7, 5, // +X
+        ];
+
+        mesh
+    }
+
+    fn signedVolume(mesh: &TriangleMesh) -> f32 {
+        mesh.indices
+            .chunks_exact(3)
+            .map(|triangle| {
+                let corner = |i: usize| &mesh.vertices[triangle[i] as usize];
+                Vec3::dot(corner(0), &Vec3::cross(corner(1), corner(2))) / 6.0
+            })
+            .sum()
+    }
+
+    #[test]
+    fn cube_faces_outwards() {
+        assert!((signedVolume(&cube(2.0)) - 8.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn repairs_flipped_cube() {
+        // Inside out, with a few triangles turned back the right way to make it inconsistent too
+        let mut mesh = cube(2.0);
+        mesh.flipWinding();
+        for face in [0, 5, 7] {
+            mesh.indices.swap(face * 3 + 1, face * 3 + 2);
+        }
+
+        repair(&mut mesh);
+        assert!((signedVolume(&mesh) - 8.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn container_turns_inside_out() {
+        let mut mesh = cube(2.0);
+        mesh.flipWinding();
+
+        let options = MeshOptions {
+            container: true,
+            ..Default::default()
+        };
+        mesh.prepare(&options);
+        assert!((signedVolume(&mesh) + 8.0).abs() < 1e-4);
+    }
+}
@@ -3,10 +3,15 @@
 
 //! Contains the main base for Puffyjuice, handling things from ticking the solver to initializing the library
 use crate::{
-    event::EventQueue,
+    collider::meshcache::MeshCache,
+    emitter::EmitterSet,
+    event::{EventContext, EventQueue},
+    field::FieldSet,
     params,
-    particle::ParticleQueue,
+    particle::{Particle, ParticleQueue},
     scene::Scene,
+    sink::SinkSet,
+    trigger::TriggerSet,
     util::{flex_buffer, flex_map},
     vec::{Vec3, Vec4},
     wrapper::solver::{FlexSolver, Solver},
@@ -20,13 +25,18 @@ use std::{
     sync::Arc,
     sync::{atomic::AtomicPtr, Mutex},
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 // TODO: Keep this, but don't make the code rely on this as if thats the current particles,
 // the user will want to spawn variable amounts of particles
-const MAX_PARTICLES: c_int = 13700;
-const MAX_COLLIDERS: c_int = 8192;
+pub const MAX_PARTICLES: c_int = 13700;
+/// How many colliders the geometry buffers can hold before they have to grow
+const DEFAULT_COLLIDER_CAPACITY: usize = 1024;
+/// The most time (in seconds) kinematic colliders move in a single tick, so a hitch doesn't teleport them
+const MAX_MOTION_STEP: f32 = 0.1;
+/// How much time (in seconds) the solver simulates every tick
+pub const TIME_STEP: f32 = 0.01 * 8.0;
 
 // hear ye hear ye
 // thy code is a travesty
@@ -71,6 +81,75 @@ fn NvFlexMakeShapeFlags(type_: NvFlexCollisionShapeType, dynamic: bool) -> i32 {
     );
 }
 
+/// Converts a channel mask as seen by Lua (channels 0-7 as bits 0-7) into FleX's shape channel bits
+fn channelBits(channels: i32) -> i32 {
+    (((channels & 0xff) as u32) << 24) as i32 & NvFlexPhase_eNvFlexPhaseShapeChannelMask
+}
+
+/// Turns queued particles into FleX particles, as long as there is room for them
+///
+/// # Safety
+/// The pointers have to be mapped FleX buffers holding `MAX_PARTICLES` elements
+unsafe fn spawnParticles(
+    particleQueue: &mut ParticleQueue,
+    queued: &[Particle],
+    particles: *mut Vec4,
+    velocity: *mut Vec3,
+    phases: *mut c_int,
+    actives: *mut c_int,
+) {
+    for particle in queued {
+        let index = particleQueue.particleCount;
+        if index >= MAX_PARTICLES {
+            break;
+        }
+
+        let mut particlePtr = particles.offset(index as isize);
+        let mut phase = phases.offset(index as isize);
+        let mut active = actives.offset(index as isize);
+        let mut velocity = velocity.offset(index as isize);
+
+        (*particlePtr) =
+            Vec4::components(particle.pos.x, particle.pos.y, particle.pos.z, 1.0 / 2.0);
+
+        *phase = NvFlexMakePhaseWithChannels(
+            particle.group,
+            NvFlexPhase_eNvFlexPhaseSelfCollide | NvFlexPhase_eNvFlexPhaseFluid,
+            channelBits(particleQueue.channels(particle.group)),
+        );
+
+        *active = index;
+        *velocity = particle.vel.clone();
+
+        particleQueue.particleCount += 1;
+    }
+}
+
+/// Removes particles by moving the last particles into their slots, so the particles stay packed
+/// `killed` has to be sorted and unique
+///
+/// # Safety
+/// The pointers have to be mapped FleX buffers holding at least `particleQueue.particleCount` elements
+unsafe fn removeParticles(
+    particleQueue: &mut ParticleQueue,
+    killed: &[usize],
+    particles: *mut Vec4,
+    velocity: *mut Vec3,
+    phases: *mut c_int,
+) {
+    // Going from the highest index down means the particle moved into a slot is never one that still has to die
+    for index in killed.iter().rev() {
+        let last = (particleQueue.particleCount - 1) as isize;
+        let index = *index as isize;
+
+        *particles.offset(index) = (*particles.offset(last)).clone();
+        *velocity.offset(index) = (*velocity.offset(last)).clone();
+        *phases.offset(index) = *phases.offset(last);
+
+        particleQueue.particleCount -= 1;
+    }
+}
+
 /// Holds the buffers of the library in a neat named fashion
 pub struct JuiceBuffers {
     /// Holds where the particles are, along with ther inverse mass
@@ -95,6 +174,58 @@ pub struct JuiceBuffers {
     geoprevrot: *mut NvFlexBuffer,
     /// Holds the colliders flags
     geoflags: *mut NvFlexBuffer,
+    /// How many colliders the geometry buffers were allocated for
+    geometryCapacity: usize,
+}
+
+impl JuiceBuffers {
+    /// (Re-)allocates the geometry buffers to hold `capacity` colliders
+    ///
+    /// # Note
+    /// The old contents are **not** copied, every collider has to be marked dirty afterwards so its geometry is rewritten
+    unsafe fn allocGeometry(&mut self, lib: FlexLibrary, capacity: usize) {
+        self.freeGeometry();
+
+        let count: c_int = capacity.try_into().unwrap();
+        self.geometry = flex_buffer!(lib, NvFlexCollisionGeometry, count);
+
+        // Buffers that also have previous variants
+        self.geopositions = flex_buffer!(lib, Vec4, count);
+        self.georotations = flex_buffer!(lib, Vec4, count);
+        self.geoprevpos = flex_buffer!(lib, Vec4, count);
+        self.geoprevrot = flex_buffer!(lib, Vec4, count);
+
+        self.geoflags = flex_buffer!(lib, c_int, count);
+        self.geometryCapacity = capacity;
+    }
+
+    /// Frees the geometry buffers, if they were allocated
+    unsafe fn freeGeometry(&mut self) {
+        if self.geometryCapacity == 0 {
+            return;
+        }
+
+        NvFlexFreeBuffer(self.geometry);
+        NvFlexFreeBuffer(self.geopositions);
+        NvFlexFreeBuffer(self.georotations);
+        NvFlexFreeBuffer(self.geoflags);
+        NvFlexFreeBuffer(self.geoprevpos);
+        NvFlexFreeBuffer(self.geoprevrot);
+        self.geometryCapacity = 0;
+    }
+
+    /// Makes sure the geometry buffers can hold `count` colliders, growing them if not
+    /// Returns `true` if the buffers were reallocated
+    unsafe fn reserveGeometry(&mut self, lib: FlexLibrary, count: usize) -> bool {
+        if count <= self.geometryCapacity {
+            return false;
+        }
+
+        // Grow geometrically so a map spawning props one by one doesn't reallocate every time
+        let capacity = count.max(self.geometryCapacity * 2).next_power_of_two();
+        self.allocGeometry(lib, capacity);
+        true
+    }
 }
 
 unsafe impl Send for JuiceBuffers {}
@@ -120,8 +251,23 @@ pub struct Juice {
     /// Thread-safe event queue, these are invoked and destroyed by the solver thread
     events: Arc<Mutex<EventQueue>>,
 
+    /// Thread-safe force fields, applied to the particles every tick
+    fields: Arc<Mutex<FieldSet>>,
+
+    /// Thread-safe particle emitters, which spawn particles every tick
+    emitters: Arc<Mutex<EmitterSet>>,
+
+    /// Thread-safe sinks, which remove the particles inside them every tick
+    sinks: Arc<Mutex<SinkSet>>,
+
+    /// Thread-safe triggers, which count the particles inside them every tick
+    triggers: Arc<Mutex<TriggerSet>>,
+
     /// Thread-safe particle queue, used to spawn particles
     particleQueue: Arc<Mutex<ParticleQueue>>,
+
+    /// Thread-safe mesh cache, lets many `Mesh` colliders share the same FleX mesh
+    meshes: Arc<Mutex<MeshCache>>,
 }
 
 impl Juice {
@@ -147,32 +293,25 @@ impl Juice {
         let phases = flex_buffer!(*lib, c_int, MAX_PARTICLES);
         let actives = flex_buffer!(*lib, c_int, MAX_PARTICLES);
 
-        // Geometry
-        let geometry = flex_buffer!(*lib, NvFlexCollisionGeometry, MAX_COLLIDERS);
-
-        // Buffers that also have previous variants
-        let geopositions = flex_buffer!(*lib, Vec4, MAX_COLLIDERS);
-        let georotations = flex_buffer!(*lib, Vec4, MAX_COLLIDERS);
-        let geoprevpos = flex_buffer!(*lib, Vec4, MAX_COLLIDERS);
-        let geoprevrot = flex_buffer!(*lib, Vec4, MAX_COLLIDERS);
-
-        let geoflags = flex_buffer!(*lib, c_int, MAX_COLLIDERS);
-
-        JuiceBuffers {
+        let mut buffers = JuiceBuffers {
             particles,
             velocity,
             phases,
             actives,
 
-            geometry,
+            geometry: std::ptr::null_mut(),
 
-            geopositions,
-            georotations,
-            geoprevpos,
-            geoprevrot,
+            geopositions: std::ptr::null_mut(),
+            georotations: std::ptr::null_mut(),
+            geoprevpos: std::ptr::null_mut(),
+            geoprevrot: std::ptr::null_mut(),
 
-            geoflags,
-        }
+            geoflags: std::ptr::null_mut(),
+            geometryCapacity: 0,
+        };
+
+        buffers.allocGeometry(*lib, DEFAULT_COLLIDER_CAPACITY);
+        buffers
     }
 
     /// Instantiates a new Juice
@@ -213,7 +352,12 @@ impl Juice {
             active: Arc::new(Mutex::new(false)),
             scene: Arc::new(Mutex::new(Scene::new())),
             events: Arc::new(Mutex::new(EventQueue::new())),
+            fields: Arc::new(Mutex::new(FieldSet::new())),
+            emitters: Arc::new(Mutex::new(EmitterSet::new())),
+            sinks: Arc::new(Mutex::new(SinkSet::new())),
+            triggers: Arc::new(Mutex::new(TriggerSet::new())),
             particleQueue: Arc::new(Mutex::new(ParticleQueue::new())),
+            meshes: Arc::new(Mutex::new(MeshCache::new())),
         }
     }
 
@@ -224,6 +368,10 @@ impl Juice {
         let activeCopyForThread = self.active.clone();
         let sceneCopy = self.scene.clone();
         let eventsCopy = self.events.clone();
+        let fieldsCopy = self.fields.clone();
+        let emittersCopy = self.emitters.clone();
+        let sinksCopy = self.sinks.clone();
+        let triggersCopy = self.triggers.clone();
         let particleQueueCopy = self.particleQueue.clone();
         let mut flexLibraryCopy = AtomicPtr::new(self.flexlib.clone());
 
@@ -238,6 +386,8 @@ impl Juice {
             // Used for spawning particles randomly so they dont look like they're all in one place
             let mut rng = rand::thread_rng();
             let mut initialized = false;
+            // When the last tick happened, kinematic colliders move by the real time between ticks
+            let mut lastTick = Instant::now();
 
             loop {
                 unsafe {
@@ -252,24 +402,45 @@ impl Juice {
 
                     if *active {
                         // We also.. you know.. need the buffers, so let's obtain a lock to them
-                        let bufferMutex =
+                        let mut bufferMutex =
                             bufferCopy.lock().expect("Couldn't lock bufferCopy (wtf?)");
                         let mut sceneMutex =
                             sceneCopy.lock().expect("Couldn't lock sceneCopy (wtf?)");
                         let mut eventsMutex =
                             eventsCopy.lock().expect("Couldn't lock eventsCopy (wtf?)");
+                        let fieldsMutex =
+                            fieldsCopy.lock().expect("Couldn't lock fieldsCopy (wtf?)");
+                        let mut emittersMutex = emittersCopy
+                            .lock()
+                            .expect("Couldn't lock emittersCopy (wtf?)");
+                        let mut sinksMutex =
+                            sinksCopy.lock().expect("Couldn't lock sinksCopy (wtf?)");
+                        let mut triggersMutex = triggersCopy
+                            .lock()
+                            .expect("Couldn't lock triggersCopy (wtf?)");
 
                         // We also need to get the particle queue
                         let mut particleQueueMutex = particleQueueCopy
                             .lock()
                             .expect("Couldn't lock particleQueueCopy (wtf?)");
 
-                        let buffers = &*bufferMutex;
+                        let buffers = &mut *bufferMutex;
                         let solver = &*solverMutex;
                         let scene = &mut *sceneMutex;
                         let events = &mut *eventsMutex;
+                        let fields = &*fieldsMutex;
+                        let emitters = &mut *emittersMutex;
+                        let sinks = &mut *sinksMutex;
+                        let triggers = &mut *triggersMutex;
                         let particleQueue = &mut *particleQueueMutex;
 
+                        let now = Instant::now();
+                        let elapsed = now
+                            .duration_since(lastTick)
+                            .as_secs_f32()
+                            .min(MAX_MOTION_STEP);
+                        lastTick = now;
+
                         let particles: *mut Vec4 = flex_map!(buffers.particles);
                         let velocity: *mut Vec3 = flex_map!(buffers.velocity);
                         let phases: *mut c_int = flex_map!(buffers.phases);
@@ -304,57 +475,123 @@ impl Juice {
                         }
                         */
 
-                        if particleQueue.particleCount < MAX_PARTICLES.try_into().unwrap() {
-                            // Create particles from the queue
-                            for particle in &particleQueue.particles {
-                                let index = particleQueue.particleCount;
+                        // Create particles from the queue, whatever doesn't fit is dropped
+                        let queued = std::mem::take(&mut particleQueue.particles);
+                        spawnParticles(
+                            particleQueue,
+                            &queued,
+                            particles,
+                            velocity,
+                            phases,
+                            actives,
+                        );
 
-                                let mut particlePtr = particles.offset(index as isize);
-                                let mut phase = phases.offset(index as isize);
-                                let mut active = actives.offset(index as isize);
-                                let mut velocity = velocity.offset(index as isize);
+                        // Emitters pour particles in on their own, as long as there is room for them
+                        let capacity =
+                            (MAX_PARTICLES - particleQueue.particleCount).max(0) as usize;
+                        let emitted = emitters.emit(TIME_STEP, scene, capacity);
+                        spawnParticles(
+                            particleQueue,
+                            &emitted,
+                            particles,
+                            velocity,
+                            phases,
+                            actives,
+                        );
 
-                                (*particlePtr) = Vec4::components(
-                                    particle.pos.x,
-                                    particle.pos.y,
-                                    particle.pos.z,
-                                    1.0 / 2.0,
-                                );
+                        // A group changed its channels, so the particles which already exist need new phases
+                        if particleQueue.channelsChanged {
+                            for i in 0..particleQueue.particleCount {
+                                let phase = phases.offset(i as isize);
+                                let group = *phase & NvFlexPhase_eNvFlexPhaseGroupMask;
 
-                                *phase = NvFlexMakePhase(
-                                    0,
-                                    NvFlexPhase_eNvFlexPhaseSelfCollide
-                                        | NvFlexPhase_eNvFlexPhaseFluid,
+                                *phase = NvFlexMakePhaseWithChannels(
+                                    group,
+                                    *phase,
+                                    channelBits(particleQueue.channels(group)),
                                 );
-
-                                *active = particleQueue.particleCount;
-                                *velocity = particle.vel.clone();
-
-                                particleQueue.particleCount += 1;
                             }
 
-                            // Clear the particle queue
-                            particleQueue.flush();
+                            particleQueue.channelsChanged = false;
                         }
 
                         // Before unmapping, flush the queue
-                        for event in &events.events {
-                            event.invoke(
-                                particles.clone(),
-                                velocity.clone(),
-                                particleQueue.particleCount.try_into().unwrap(),
+                        if events.hasDue() {
+                            let count: usize = particleQueue.particleCount.try_into().unwrap();
+                            let mut context = EventContext::new(
+                                std::slice::from_raw_parts_mut(particles, count),
+                                std::slice::from_raw_parts_mut(velocity, count),
+                                std::slice::from_raw_parts_mut(phases, count),
+                                std::slice::from_raw_parts(actives, count),
+                                scene,
+                                events.tick,
+                                events.time,
+                            );
+
+                            events.invoke(&mut context);
+
+                            // Particles only come and go once every event is done, so indices stay put while they run
+                            let (spawned, killed) = context.into_changes();
+                            removeParticles(particleQueue, &killed, particles, velocity, phases);
+                            spawnParticles(
+                                particleQueue,
+                                &spawned,
+                                particles,
+                                velocity,
+                                phases,
+                                actives,
                             );
                         }
 
                         events.flush();
 
+                        // Fields push on whatever particles are left once the events are done
+                        if !fields.fields.is_empty() {
+                            let count: usize = particleQueue.particleCount.try_into().unwrap();
+                            fields.apply(
+                                std::slice::from_raw_parts(particles, count),
+                                std::slice::from_raw_parts_mut(velocity, count),
+                                TIME_STEP,
+                                events.time,
+                            );
+                        }
+
+                        // Sinks swallow whatever ended up inside them
+                        if !sinks.sinks.is_empty() {
+                            let count: usize = particleQueue.particleCount.try_into().unwrap();
+                            let absorbed =
+                                sinks.absorb(std::slice::from_raw_parts(particles, count), scene);
+                            removeParticles(particleQueue, &absorbed, particles, velocity, phases);
+                        }
+
+                        // Triggers count whatever is left, exactly what the solver is about to simulate
+                        if !triggers.triggers.is_empty() {
+                            let count: usize = particleQueue.particleCount.try_into().unwrap();
+                            triggers.update(
+                                std::slice::from_raw_parts(particles, count),
+                                std::slice::from_raw_parts(velocity, count),
+                                events.tick,
+                            );
+                        }
+
                         NvFlexUnmap(buffers.particles);
                         NvFlexUnmap(buffers.velocity);
                         NvFlexUnmap(buffers.phases);
                         NvFlexUnmap(buffers.actives);
 
                         // Work on geometries next
-                        // TODO: do this
+                        // Kinematic colliders move on their own, and attached colliders follow their parents,
+                        // so they have to be moved before anything is written
+                        scene.advanceMotion(elapsed);
+                        scene.resolveHierarchy();
+
+                        // Colliders may have been added since the last tick, so make sure all of their shapes fit
+                        if buffers.reserveGeometry(*flexLibraryCopy.get_mut(), scene.shapeCount()) {
+                            for record in scene.objects.values_mut() {
+                                record.dirty = true;
+                            }
+                        }
+
                         // Map some geometric buffers we need
                         let geometry: *mut NvFlexCollisionGeometry = flex_map!(buffers.geometry);
                         let geopositions: *mut Vec4 = flex_map!(buffers.geopositions);
@@ -364,26 +601,64 @@ impl Juice {
                         let geoprevpos: *mut Vec4 = flex_map!(buffers.geoprevpos);
                         let geoprevrot: *mut Vec4 = flex_map!(buffers.geoprevrot);
 
-                        for (index, record) in (scene.objects.iter_mut().enumerate()) {
-                            let mut collider = &mut record.collider;
+                        // Disabled colliders are skipped, so the enabled ones are packed into the first slots
+                        let mut shapeCount: usize = 0;
+
+                        for record in scene.objects.values_mut() {
+                            if !record.collider.isEnabled() {
+                                // Keep the motion history fresh, so re-enabling doesn't sweep through the fluid
+                                record.collider.storePrevious();
+                                continue;
+                            }
+
+                            // Colliders made of several shapes take up consecutive slots
+                            let index = shapeCount;
+                            shapeCount += record.collider.shapeCount();
+
+                            // Geometry is written once per slot, so rewrite it whenever the collider moved to
+                            // a different slot or its shape changed
+                            if record.needsGeometry(index) {
+                                let fresh = record.slot.is_none();
+                                let collider = &mut record.collider;
 
-                            if !collider.isInitialized() {
                                 collider.set_library(*flexLibraryCopy.get_mut());
                                 collider.initializeGeometry(index.try_into().unwrap(), geometry);
+
+                                if fresh {
+                                    // Don't let a fresh collider sweep all the way from the origin to where it was placed
+                                    collider.storePrevious();
+                                }
+
+                                record.assignSlot(index);
                             }
 
+                            let collider = &mut record.collider;
+
                             // Update positions.. rotations.. flags.. everything!!
-                            let geoPos = geopositions.offset(index as isize);
-                            let geoRot = georotations.offset(index as isize);
-                            let geoFlags = geoflags.offset(index as isize);
-                            let geoPrevPos = geoprevpos.offset(index as isize);
-                            let geoPrevRot = geoprevrot.offset(index as isize);
-
-                            *geoPos = Vec4::from(&collider.position());
-                            *geoRot = collider.rotation();
-                            *geoFlags = NvFlexMakeShapeFlags(collider.getShapeFlag(), false);
-                            *geoPrevPos = Vec4::from(&collider.prev_position());
-                            *geoPrevRot = collider.prev_rotation();
+                            for shapeIndex in 0..collider.shapeCount() {
+                                let shape = collider.shape(shapeIndex);
+                                let slot = (index + shapeIndex) as isize;
+
+                                let geoPos = geopositions.offset(slot);
+                                let geoRot = georotations.offset(slot);
+                                let geoFlags = geoflags.offset(slot);
+                                let geoPrevPos = geoprevpos.offset(slot);
+                                let geoPrevRot = geoprevrot.offset(slot);
+
+                                *geoPos = Vec4::from(&shape.position);
+                                *geoRot = shape.rotation;
+                                *geoFlags = NvFlexMakeShapeFlagsWithChannels(
+                                    shape.shapeType,
+                                    collider.isDynamic(),
+                                    channelBits(collider.channels()),
+                                );
+                                *geoPrevPos = Vec4::from(&shape.prev_position);
+                                *geoPrevRot = shape.prev_rotation;
+                            }
+
+                            // However many times Lua moved the collider since the last tick, this tick's motion
+                            // starts from where the collider is right now
+                            collider.storePrevious();
                         }
 
                         // Unmap the geometry buffers
@@ -411,10 +686,12 @@ impl Juice {
                             buffers.geoprevpos,
                             buffers.geoprevrot,
                             buffers.geoflags,
-                            scene.len().try_into().unwrap(),
+                            shapeCount.try_into().unwrap(),
                         );
 
-                        NvFlexUpdateSolver(solver.get(), 0.01 * 8.0, 3, false);
+                        NvFlexUpdateSolver(solver.get(), TIME_STEP, 3, false);
+                        events.tick += 1;
+                        events.time += TIME_STEP as f64;
 
                         NvFlexGetParticles(solver.get(), buffers.particles, std::ptr::null_mut());
                         NvFlexGetVelocities(solver.get(), buffers.velocity, std::ptr::null_mut());
@@ -464,16 +741,22 @@ impl Juice {
 
         // Now, the program flow is programmed in a way where this is a safe spot to completely shut down the solver
         let solverMutex = solverCopy.lock().expect("Couldn't lock solverCopy (wtf?)");
-        let bufferMutex = bufferCopy.lock().expect("Couldn't lock bufferCopy (wtf?)");
+        let mut bufferMutex = bufferCopy.lock().expect("Couldn't lock bufferCopy (wtf?)");
         let mut sceneMutex = sceneCopy.lock().expect("Couldn't lock sceneCopy (wtf?)");
 
         let solver = &*solverMutex;
-        let bufferMutex = &*bufferMutex;
+        let bufferMutex = &mut *bufferMutex;
         let scene = &mut *sceneMutex;
 
         // Drop all the objects, they'll handle it themselves
         scene.objects.clear();
 
+        // Meshes which were registered but never instanced are still alive, so drop them too
+        self.meshes
+            .lock()
+            .expect("Couldn't lock meshes (wtf?)")
+            .clear();
+
         // Remove buffers, then destroy solver
         NvFlexFreeBuffer(bufferMutex.particles);
         NvFlexFreeBuffer(bufferMutex.velocity);
@@ -481,12 +764,7 @@ impl Juice {
         NvFlexFreeBuffer(bufferMutex.actives);
 
         // Geometry
-        NvFlexFreeBuffer(bufferMutex.geometry);
-        NvFlexFreeBuffer(bufferMutex.geopositions);
-        NvFlexFreeBuffer(bufferMutex.georotations);
-        NvFlexFreeBuffer(bufferMutex.geoflags);
-        NvFlexFreeBuffer(bufferMutex.geoprevpos);
-        NvFlexFreeBuffer(bufferMutex.geoprevrot);
+        bufferMutex.freeGeometry();
 
         NvFlexDestroySolver(solver.get());
 
@@ -536,6 +814,20 @@ impl Juice {
         positions
     }
 
+    /// Grows the geometry buffers ahead of time, so they can hold at least `count` colliders
+    /// this does perform mutex magic, so expect for it to block
+    pub unsafe fn reserveColliders(&self, count: usize) {
+        let mut bufferMutex = self.buffers.lock().expect("Couldn't lock buffers (wtf?)");
+        let buffers = &mut *bufferMutex;
+
+        if buffers.reserveGeometry(self.flexlib, count) {
+            let mut sceneMutex = self.scene.lock().expect("Couldn't lock scene (wtf?)");
+            for record in sceneMutex.objects.values_mut() {
+                record.dirty = true;
+            }
+        }
+    }
+
     /// Returns the FlexLibrary
     pub fn get_lib(&self) -> FlexLibrary {
         self.flexlib.clone()
@@ -551,10 +843,35 @@ impl Juice {
         self.events.clone()
     }
 
+    /// Returns a `Arc<Mutex<FieldSet>>` to the caller, allowing for proper multithreaded access
+    pub fn get_fields(&self) -> Arc<Mutex<FieldSet>> {
+        self.fields.clone()
+    }
+
+    /// Returns a `Arc<Mutex<EmitterSet>>` to the caller, allowing for proper multithreaded access
+    pub fn get_emitters(&self) -> Arc<Mutex<EmitterSet>> {
+        self.emitters.clone()
+    }
+
+    /// Returns a `Arc<Mutex<SinkSet>>` to the caller, allowing for proper multithreaded access
+    pub fn get_sinks(&self) -> Arc<Mutex<SinkSet>> {
+        self.sinks.clone()
+    }
+
+    /// Returns a `Arc<Mutex<TriggerSet>>` to the caller, allowing for proper multithreaded access
+    pub fn get_triggers(&self) -> Arc<Mutex<TriggerSet>> {
+        self.triggers.clone()
+    }
+
     /// Returns a `Arc<Mutex<ParticleQueue>` to the caller, allowing for proper multithreaded access
     pub fn get_particle_queue(&self) -> Arc<Mutex<ParticleQueue>> {
         self.particleQueue.clone()
     }
+
+    /// Returns a `Arc<Mutex<MeshCache>>` to the caller, allowing for proper multithreaded access
+    pub fn get_mesh_cache(&self) -> Arc<Mutex<MeshCache>> {
+        self.meshes.clone()
+    }
 }
 
 unsafe impl Send for Juice {}
@@ -1,9 +1,19 @@
 #![allow(non_snake_case)]
 use std::mem::MaybeUninit;
-
-use event::setparticle::SetParticleEvent;
+use std::sync::Arc;
+
+use event::{
+    explosion::ExplosionEvent,
+    impulse::ImpulseEvent,
+    schedule::{Clock, Schedule},
+    setparticle::SetParticleEvent,
+    teleport::TeleportEvent,
+    velocity::{VelocityChange, VelocityEvent},
+    vortex::VortexEvent,
+    Event,
+};
 use flexgen::*;
-use rglua::{prelude::*, userdata::Vector};
+use rglua::prelude::*;
 
 pub mod util;
 
@@ -12,22 +22,52 @@ pub mod wrapper {
 }
 
 pub mod collider;
+pub mod emitter;
 pub mod event;
+pub mod field;
+pub mod geometry;
+pub mod loader;
+pub mod motion;
 pub mod params;
 pub mod particle;
+pub mod region;
 pub mod scene;
+pub mod sink;
+pub mod slotmap;
+pub mod trigger;
 pub mod vec;
 
 mod juice;
+mod luautil;
 
-use juice::Juice;
+use juice::{Juice, MAX_PARTICLES, TIME_STEP};
 
 use once_cell::sync::Lazy;
 use vec::{Vec3, Vec4};
 
 use crate::{
-    collider::{capsule::Capsule, mesh::Mesh},
-    particle::Particle,
+    collider::{
+        capsule::Capsule, compound::Compound, convex::Convex, cuboid::Cuboid,
+        heightfield::Heightfield, mesh::Mesh, meshcache::SharedMesh, Collider,
+    },
+    emitter::{Emitter, EmitterError, EmitterMode, EmitterShape},
+    field::{Falloff, FieldError, FieldKind, ForceField},
+    geometry::{MeshOptions, TriangleMesh},
+    loader::{
+        bsp::{self, BspOptions},
+        loadMesh,
+    },
+    luautil::{
+        checkRotation, checkVector, pushQuat, pushVector, readRotation, readVector, tableNumber,
+        tableString, ArgError, EntryError,
+    },
+    motion::{Keyframe, KeyframeTrack, Motion},
+    particle::{Particle, ALL_CHANNELS, MAX_GROUP},
+    region::Region,
+    scene::{Attachment, SceneError, TransformUpdate},
+    sink::{Sink, SinkError, SinkVolume, DEFAULT_MARGIN},
+    slotmap::Handle,
+    trigger::{Trigger, TriggerError},
     vec::Quat,
 };
 
@@ -66,38 +106,20 @@ fn getParticlePositions(state: LuaState) -> Result<i32, std::io::Error> {
     Ok(1)
 }
 
-/// Fetches a number from a field in a table on the top of the stack
-/// # Warning
-/// This is to be only primarily used within the collider lua interface code!!
-macro_rules! getTableNumber {
-    ($state:expr, $varName:ident, $key:expr) => {
-        lua_getfield($state, -1, cstr!($key));
-        let $varName = lua_tonumber($state, -1) as f32;
-        lua_pop($state, 1);
-    };
+/// Reads a collider ID from the stack, IDs are handed to Lua as plain numbers
+fn getColliderId(state: LuaState, index: i32) -> Result<Handle, SceneError> {
+    readHandle(state, index).ok_or(SceneError::InvalidId(lua_tonumber(state, index)))
 }
-// Mesh related functions
-#[lua_function]
-fn createCollider(state: LuaState) -> Result<i32, std::io::Error> {
-    // We expect arguments like this: table (mesh vertices), lower bound, upper bound (each tables with x,y,z)
-    // Consume from the top of the stack, upper bound first
-    getTableNumber!(state, upper_bound_x, "x");
-    getTableNumber!(state, upper_bound_y, "y");
-    getTableNumber!(state, upper_bound_z, "z");
-    // Pop the upper bound off
-    lua_pop(state, 1);
-
-    // Now consume the lower bound
-    getTableNumber!(state, lower_bound_x, "x");
-    getTableNumber!(state, lower_bound_y, "y");
-    getTableNumber!(state, lower_bound_z, "z");
-    // Pop the lower bound off
-    lua_pop(state, 1);
 
-    let upper_bound = Vec3::components(upper_bound_x, upper_bound_y, upper_bound_z);
-    let lower_bound = Vec3::components(lower_bound_x, lower_bound_y, lower_bound_z);
+/// Reads a handle previously pushed to Lua, `None` if the number can't be one
+fn readHandle(state: LuaState, index: i32) -> Option<Handle> {
+    Handle::from_number(lua_tonumber(state, index))
+}
 
-    // Now we expect a table of vertices
+/// Reads a table of vertices (each a `Vector` or a table with x, y, z) from the top of the stack, leaving the table on the stack
+///
+/// `argument` is the argument index of the table, which is reported if a vertex is wrong
+fn readVertices(state: LuaState, argument: i32) -> Result<Vec<Vec4>, EntryError> {
     let tableLength = lua_objlen(state, -1);
     let mut vertices: Vec<Vec4> = Vec::with_capacity(tableLength as usize);
 
@@ -105,226 +127,2056 @@ fn createCollider(state: LuaState) -> Result<i32, std::io::Error> {
         // Lua indices go 1, 2, 3, ...
         // unlike normal indices, which are 0, 1, 2, ...
         let real_index = i + 1;
-        // Vertices are also tables with {x, y, z} (to excuse for the lack of Vector userdata support currently in crate rglua)
         lua_pushnumber(state, real_index as f64);
         lua_gettable(state, -2);
 
-        // The vertex is now at the top of the stack, so we can do our normal thing here
-        getTableNumber!(state, vert_x, "x");
-        getTableNumber!(state, vert_y, "y");
-        getTableNumber!(state, vert_z, "z");
+        // The vertex is now at the top of the stack
+        let vertex = readVector(state, -1).ok_or(EntryError {
+            index: argument,
+            entry: real_index as usize,
+            expected: "Vector vertex",
+        })?;
 
         // Pop the vertex off the stack
         lua_pop(state, 1);
 
         // Push the vertex into the vector
-        vertices.push(Vec4::components(vert_x, vert_y, vert_z, 1.0 / 2.0));
+        vertices.push(Vec4::components(vertex.x, vertex.y, vertex.z, 1.0 / 2.0));
         // Rinse and repeat
     }
 
+    Ok(vertices)
+}
+
+// Mesh related functions
+#[lua_function]
+fn createCollider(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: table (mesh vertices), lower bound, upper bound (each a Vector),
+    // mesh options (optional, see readMeshOptions)
+    let lower_bound = checkVector(state, 2)?;
+    let upper_bound = checkVector(state, 3)?;
+    let options = if lua_type(state, 4) == LUA_TTABLE {
+        Some(readMeshOptions(state, 4))
+    } else {
+        None
+    };
+
+    // Only leave the table of vertices on the stack
+    lua_settop(state, 1);
+    let vertices = readVertices(state, 1)?;
+
     // Pop the table off the stack
     lua_pop(state, 1);
-    // We have all our data now, it's time to instantiate a Boxed Mesh Collider, insert it into the singleton's scene, and return the index
-    printgm!(
-        state,
-        "Entering unsafe Rust territory, a crash is unlikely but prepare.."
-    );
 
-    let collider = unsafe {
-        Box::new(Mesh::new(
+    // Prepared meshes are their own, only untouched vertex lists are shared through the cache
+    if let Some(options) = options {
+        let mesh = TriangleMesh {
+            indices: (0..vertices.len() as u32).collect(),
+            vertices: vertices
+                .iter()
+                .map(|v| Vec3::components(v.x, v.y, v.z))
+                .collect(),
+        };
+
+        return addPreparedMesh(state, mesh, &options);
+    }
+
+    // Identical vertex lists share the same FleX mesh
+    let meshPtr = JUICE_SINGLETON.get_mesh_cache();
+    // Block while waiting for access to the mutex
+    let mut meshLock = meshPtr.lock().expect("Could not lock mesh cache (wtf?)");
+    let meshCache = &mut *meshLock;
+
+    let sharedMesh =
+        meshCache.instance_contents(&vertices, lower_bound.clone(), upper_bound.clone(), || {
+            // We have all our data now, it's time to upload it to FleX
+            printgm!(
+                state,
+                "Entering unsafe Rust territory, a crash is unlikely but prepare.."
+            );
+
+            unsafe {
+                SharedMesh::from_triangle_list(
+                    JUICE_SINGLETON.get_lib(),
+                    &vertices,
+                    lower_bound,
+                    upper_bound,
+                )
+            }
+        });
+    let collider = Box::new(Mesh::new(sharedMesh));
+
+    let scenePtr = JUICE_SINGLETON.get_scene();
+    // Block while waiting for access to the mutex
+    let mut sceneLock = scenePtr.lock().expect("Could not lock scene (wtf?)");
+    let sceneObject = &mut *sceneLock;
+
+    let idx = sceneObject.add(collider)?;
+
+    // Finally, finished!!
+    // TODO: Make sure to properly typecheck this function
+
+    // Return the ID of the collider
+    lua_pushnumber(state, idx.to_raw() as f64);
+    Ok(1)
+}
+
+#[lua_function]
+fn registerMesh(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: name, table (mesh vertices)
+    let name = rstr!(luaL_checklstring(state, 1, std::ptr::null_mut())).to_string();
+
+    // Every 3 vertices make up a triangle, anything else isn't a mesh
+    let vertexCount = if lua_type(state, 2) == LUA_TTABLE {
+        lua_objlen(state, 2)
+    } else {
+        0
+    };
+    if vertexCount == 0 || vertexCount % 3 != 0 {
+        return Err(Box::new(ArgError {
+            index: 2,
+            expected: "vertex table with a multiple of 3 entries",
+        }));
+    }
+
+    // Only leave the name and the table of vertices on the stack
+    lua_settop(state, 2);
+    let vertices = readVertices(state, 2)?;
+
+    // Pop the table and the name off
+    lua_pop(state, 2);
+
+    let meshPtr = JUICE_SINGLETON.get_mesh_cache();
+    // Block while waiting for access to the mutex
+    let mut meshLock = meshPtr.lock().expect("Could not lock mesh cache (wtf?)");
+    let meshCache = &mut *meshLock;
+
+    // Registering is only done once, the existing mesh is kept around
+    if meshCache.contains(&name) {
+        lua_pushboolean(state, 0);
+        return Ok(1);
+    }
+
+    let (lower_bound, upper_bound) = SharedMesh::compute_bounds(&vertices);
+    let mesh = unsafe {
+        SharedMesh::from_triangle_list(
             JUICE_SINGLETON.get_lib(),
-            vertices,
+            &vertices,
             lower_bound,
             upper_bound,
-        ))
+        )
     };
 
+    meshCache.register(&name, mesh);
+
+    lua_pushboolean(state, 1);
+    Ok(1)
+}
+
+#[lua_function]
+fn createMeshInstance(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: name
+    let name = rstr!(luaL_checklstring(state, 1, std::ptr::null_mut())).to_string();
+
+    let meshPtr = JUICE_SINGLETON.get_mesh_cache();
+    // Block while waiting for access to the mutex
+    let mut meshLock = meshPtr.lock().expect("Could not lock mesh cache (wtf?)");
+    let meshCache = &mut *meshLock;
+
+    let sharedMesh = meshCache.instance(&name).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("No mesh is registered as \"{}\"", name),
+        )
+    })?;
+
     let scenePtr = JUICE_SINGLETON.get_scene();
     // Block while waiting for access to the mutex
     let mut sceneLock = scenePtr.lock().expect("Could not lock scene (wtf?)");
     let sceneObject = &mut *sceneLock;
 
-    let idx = sceneObject.add(collider);
-
-    // Finally, finished!!
-    // TODO: Make sure to properly typecheck this function
+    let idx = sceneObject.add(Box::new(Mesh::new(sharedMesh)))?;
 
-    // Return the index of the collider
-    lua_pushnumber(state, idx as f64);
+    // Return the ID of the collider
+    lua_pushnumber(state, idx.to_raw() as f64);
     Ok(1)
 }
 
 // Collider position & rotation functions
 #[lua_function]
-fn setColliderPos(state: LuaState) -> Result<i32, std::io::Error> {
-    // We expect arguments like this: collider index, x, y, z
-    let collider_idx = lua_tonumber(state, -4) as usize;
-    let x = lua_tonumber(state, -3) as f32;
-    let y = lua_tonumber(state, -2) as f32;
-    let z = lua_tonumber(state, -1) as f32;
+fn setColliderPos(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: collider ID, Vector (or x, y, z)
+    let collider_id = getColliderId(state, 1)?;
+    let position = if lua_type(state, 2) == LUA_TNUMBER {
+        Vec3::components(
+            lua_tonumber(state, 2) as f32,
+            lua_tonumber(state, 3) as f32,
+            lua_tonumber(state, 4) as f32,
+        )
+    } else {
+        checkVector(state, 2)?
+    };
 
     let scenePtr = JUICE_SINGLETON.get_scene();
     // Block while waiting for access to the mutex
     let mut sceneLock = scenePtr.lock().expect("Could not lock scene (wtf?)");
     let sceneObject = &mut *sceneLock;
 
-    let collider = sceneObject.get(collider_idx.try_into().unwrap());
-    if let Some(collider) = collider {
-        collider.setPosition(Vec3::components(x, y, z));
-    } else {
-        printgm!(state, "Could not find collider with index {}", collider_idx);
-    }
+    let collider = sceneObject.get(collider_id)?;
+    collider.setPosition(position);
 
     // Finally, finished!!
     Ok(0)
 }
 
 #[lua_function]
-fn setColliderRot(state: LuaState) -> Result<i32, std::io::Error> {
-    // We expect the arguments like this: collider index, x, y, z, w
-    let collider_idx = lua_tonumber(state, -5) as usize;
-    let x = lua_tonumber(state, -4) as f32;
-    let y = lua_tonumber(state, -3) as f32;
-    let z = lua_tonumber(state, -2) as f32;
-    let w = lua_tonumber(state, -1) as f32;
+fn setColliderRot(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect the arguments like this: collider ID, Angle (or a quaternion as x, y, z, w)
+    let collider_id = getColliderId(state, 1)?;
+    let rotation = if lua_type(state, 2) == LUA_TNUMBER {
+        Quat::components(
+            lua_tonumber(state, 2) as f32,
+            lua_tonumber(state, 3) as f32,
+            lua_tonumber(state, 4) as f32,
+            lua_tonumber(state, 5) as f32,
+        )
+    } else {
+        checkRotation(state, 2)?
+    };
 
     let scenePtr = JUICE_SINGLETON.get_scene();
     // Block while waiting for access to the mutex
     let mut sceneLock = scenePtr.lock().expect("Could not lock scene (wtf?)");
     let sceneObject = &mut *sceneLock;
 
-    let collider = sceneObject.get(collider_idx.try_into().unwrap());
-    if let Some(collider) = collider {
-        collider.setRotation(Quat::components(x, y, z, w));
-    } else {
-        printgm!(state, "Could not find collider with index {}", collider_idx);
+    let collider = sceneObject.get(collider_id)?;
+    collider.setRotation(rotation);
+
+    Ok(0)
+}
+
+/// Reads a table of {id, pos, ang} tables from the top of the stack, `pos` and `ang` may be left out
+fn readTransformUpdates(state: LuaState) -> Result<Vec<TransformUpdate>, SceneError> {
+    let tableLength = lua_objlen(state, -1);
+    let mut updates: Vec<TransformUpdate> = Vec::with_capacity(tableLength as usize);
+
+    for i in 0..tableLength {
+        // Lua indices go 1, 2, 3, ...
+        lua_pushnumber(state, (i + 1) as f64);
+        lua_gettable(state, -2);
+
+        // The update is at the top of the stack, its members are positional
+        lua_pushnumber(state, 1.0);
+        lua_gettable(state, -2);
+        let id = getColliderId(state, -1)?;
+        lua_pop(state, 1);
+
+        lua_pushnumber(state, 2.0);
+        lua_gettable(state, -2);
+        let position = readVector(state, -1);
+        lua_pop(state, 1);
+
+        lua_pushnumber(state, 3.0);
+        lua_gettable(state, -2);
+        let rotation = readRotation(state, -1);
+        // Pop off the rotation, and the update itself
+        lua_pop(state, 2);
+
+        updates.push(TransformUpdate {
+            id,
+            position,
+            rotation,
+        });
     }
 
+    Ok(updates)
+}
+
+#[lua_function]
+fn setColliderTransforms(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: a table of {id, pos, ang} tables, or a string of packed transforms
+    // The packed string is the fast path, see `TransformUpdate::PACKED_SIZE` for its layout
+    let updates = if lua_type(state, -1) == LUA_TSTRING {
+        let mut length: usize = 0;
+        let data = lua_tolstring(state, -1, &mut length);
+        let bytes = unsafe { std::slice::from_raw_parts(data as *const u8, length) };
+
+        TransformUpdate::decodePacked(bytes)?
+    } else {
+        readTransformUpdates(state)?
+    };
+
+    let scenePtr = JUICE_SINGLETON.get_scene();
+    // Block while waiting for access to the mutex, once for the whole batch
+    let mut sceneLock = scenePtr.lock().expect("Could not lock scene (wtf?)");
+    let sceneObject = &mut *sceneLock;
+
+    sceneObject.applyTransforms(updates)?;
+
     Ok(0)
 }
 
 #[lua_function]
-fn removeCollider(state: LuaState) -> Result<i32, std::io::Error> {
-    let collider_idx = lua_tonumber(state, -1) as usize;
+fn setColliderDynamic(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect the arguments like this: collider ID, dynamic
+    let collider_id = getColliderId(state, 1)?;
+    let dynamic = lua_toboolean(state, 2) != 0;
 
     let scenePtr = JUICE_SINGLETON.get_scene();
     // Block while waiting for access to the mutex
     let mut sceneLock = scenePtr.lock().expect("Could not lock scene (wtf?)");
     let sceneObject = &mut *sceneLock;
 
-    if sceneObject.isValid(collider_idx.try_into().unwrap()) {
-        sceneObject.remove(collider_idx.try_into().unwrap());
-    } else {
-        printgm!(
-            state,
-            "Could not find collider with index {}, len: {}",
-            collider_idx,
-            sceneObject.len()
-        );
-    }
+    let collider = sceneObject.get(collider_id)?;
+    collider.setDynamic(dynamic);
 
     Ok(0)
 }
 
-// Collider-specific related down here
+/// Reads a channel mask from `index`, raising an error unless it only uses the 8 channels FleX has
+fn checkChannels(state: LuaState, index: i32) -> Result<i32, ArgError> {
+    let channels = luaL_checkinteger(state, index);
+    if !(0..=ALL_CHANNELS as isize).contains(&channels) {
+        return Err(ArgError {
+            index,
+            expected: "channel mask from 0 to 255",
+        });
+    }
+
+    Ok(channels as i32)
+}
+
+/// Raises an error unless `group` (argument `index`) is a particle group FleX can store
+fn checkGroup(index: i32, group: isize) -> Result<i32, ArgError> {
+    if !(0..=MAX_GROUP as isize).contains(&group) {
+        return Err(ArgError {
+            index,
+            expected: "particle group from 0 to 1048575",
+        });
+    }
+
+    Ok(group as i32)
+}
+
 #[lua_function]
-fn spawnPlayerCollider(state: LuaState) -> Result<i32, std::io::Error> {
-    let collider = unsafe { Box::new(Capsule::new(12.0, 10.0)) };
+fn setColliderChannels(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect the arguments like this: collider ID, channel mask
+    let collider_id = getColliderId(state, 1)?;
+    let channels = checkChannels(state, 2)?;
 
     let scenePtr = JUICE_SINGLETON.get_scene();
     // Block while waiting for access to the mutex
     let mut sceneLock = scenePtr.lock().expect("Could not lock scene (wtf?)");
     let sceneObject = &mut *sceneLock;
 
-    let idx = sceneObject.add(collider);
+    let collider = sceneObject.get(collider_id)?;
+    collider.setChannels(channels);
 
-    // Finally, finished!!
-    // Return the index of the collider
-    lua_pushnumber(state, idx as f64);
-    Ok(1)
+    Ok(0)
 }
 
 #[lua_function]
-fn setParticles(state: LuaState) -> Result<i32, std::io::Error> {
-    getTableNumber!(state, pos_x, "x");
-    getTableNumber!(state, pos_y, "y");
-    getTableNumber!(state, pos_z, "z");
+fn setColliderEnabled(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect the arguments like this: collider ID, enabled
+    let collider_id = getColliderId(state, 1)?;
+    let enabled = lua_toboolean(state, 2) != 0;
 
-    // Pop off the position
-    lua_pop(state, 1);
+    let scenePtr = JUICE_SINGLETON.get_scene();
+    // Block while waiting for access to the mutex
+    let mut sceneLock = scenePtr.lock().expect("Could not lock scene (wtf?)");
+    let sceneObject = &mut *sceneLock;
 
-    let particlePos = Vec4::components(pos_x, pos_y, pos_z, 1.0 / 2.0);
-    let particleEvent = Box::new(SetParticleEvent {
-        position: particlePos,
-    });
+    let collider = sceneObject.get(collider_id)?;
+    collider.setEnabled(enabled);
 
-    let eventPtr = JUICE_SINGLETON.get_event_queue();
+    Ok(0)
+}
+
+#[lua_function]
+fn setColliderParent(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect the arguments like this: collider ID, parent ID (nil to detach), local offset, local rotation
+    // The offset and rotation are optional, an attached collider ignores its own SetColliderPos/Rot
+    let collider_id = getColliderId(state, 1)?;
+
+    let attachment = if lua_type(state, 2) <= LUA_TNIL {
+        None
+    } else {
+        Some(Attachment {
+            parent: getColliderId(state, 2)?,
+            offset: readVector(state, 3).unwrap_or(Vec3::new()),
+            rotation: readRotation(state, 4).unwrap_or(Quat::identity()),
+        })
+    };
+
+    let scenePtr = JUICE_SINGLETON.get_scene();
     // Block while waiting for access to the mutex
-    let mut eventLock = eventPtr.lock().expect("Could not lock event queue (wtf?)");
-    let eventObject = &mut *eventLock;
-    eventObject.add_event(particleEvent);
+    let mut sceneLock = scenePtr.lock().expect("Could not lock scene (wtf?)");
+    let sceneObject = &mut *sceneLock;
+
+    sceneObject.setParent(collider_id, attachment)?;
+
     Ok(0)
 }
 
 #[lua_function]
-fn addParticles(state: LuaState) -> Result<i32, std::io::Error> {
-    // We expect a table, that.. contains tables
-    // and the table inside of the table is a lua particle struct, simply having 2 members:
-    // pos and vel, both vectors
+fn setColliderVelocity(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect the arguments like this: collider ID, linear velocity (Vector), angular velocity (Vector)
+    // The angular velocity is an axis scaled by degrees per second, both velocities are optional
+    // Colliders move in real seconds, unlike emitters, fields and schedules which run on simulated time
+    let collider_id = getColliderId(state, 1)?;
+    let linear = readVector(state, 2).unwrap_or(Vec3::new());
+    let angular = readVector(state, 3).unwrap_or(Vec3::new());
 
-    // We want a table because if we were to perhaps, make a particle cube or sphere,
-    // we'd have to invoke this function THOUSANDS of times, and we'd have to do it
-    // in a loop, so.. we'll just do it in a table
+    let motion = Motion::Velocity { linear, angular };
 
-    // Get the particle queue pointer
-    let particlePtr = JUICE_SINGLETON.get_particle_queue();
+    let scenePtr = JUICE_SINGLETON.get_scene();
     // Block while waiting for access to the mutex
-    let mut particleLock = particlePtr
-        .lock()
-        .expect("Could not lock particle queue (wtf?)");
-    let particleObject = &mut *particleLock;
+    let mut sceneLock = scenePtr.lock().expect("Could not lock scene (wtf?)");
+    let sceneObject = &mut *sceneLock;
+
+    sceneObject.setMotion(collider_id, Some(motion))?;
+
+    Ok(0)
+}
+
+#[lua_function]
+fn setColliderKeyframes(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect the arguments like this: collider ID, table of keyframes, loop (optional)
+    // Each keyframe looks like {time = real seconds, pos = Vector, ang = Angle (optional)}
+    let collider_id = getColliderId(state, 1)?;
+    let looping = lua_toboolean(state, 3) != 0;
+
+    if lua_type(state, 2) != LUA_TTABLE {
+        return Err(Box::new(ArgError {
+            index: 2,
+            expected: "table of keyframes",
+        }));
+    }
+    lua_settop(state, 2);
 
     let tableLength = lua_objlen(state, -1);
-    let mut particles: Vec<Particle> = Vec::with_capacity(tableLength as usize);
+    let mut keys: Vec<Keyframe> = Vec::with_capacity(tableLength as usize);
 
     for i in 0..tableLength {
         // Lua indices go 1, 2, 3, ...
-        // unlike normal indices, which are 0, 1, 2, ...
         let real_index = i + 1;
         lua_pushnumber(state, real_index as f64);
         lua_gettable(state, -2);
 
-        // The particle structure is at the top of the stack, lets push it and then pop it, for pos and vel
+        let invalid = EntryError {
+            index: 2,
+            entry: real_index as usize,
+            expected: "keyframe with a time and a pos Vector",
+        };
+
+        if lua_type(state, -1) != LUA_TTABLE {
+            return Err(Box::new(invalid));
+        }
+
+        let time = tableNumber(state, -1, cstr!("time"));
+
         lua_getfield(state, -1, cstr!("pos"));
-        getTableNumber!(state, pos_x, "x");
-        getTableNumber!(state, pos_y, "y");
-        getTableNumber!(state, pos_z, "z");
-        // Pop off the pos
+        let position = readVector(state, -1);
         lua_pop(state, 1);
-        // Push the vel
-        lua_getfield(state, -1, cstr!("vel"));
-        getTableNumber!(state, vel_x, "x");
-        getTableNumber!(state, vel_y, "y");
-        getTableNumber!(state, vel_z, "z");
-        // Pop off the vel, and while we're at it, additionally pop the particle struct
+
+        lua_getfield(state, -1, cstr!("ang"));
+        let rotation = readRotation(state, -1).unwrap_or(Quat::identity());
+        // Pop off the rotation and the keyframe
         lua_pop(state, 2);
 
-        // Construct our Particle
-        let particle = Particle {
-            pos: Vec3::components(pos_x, pos_y, pos_z),
-            vel: Vec3::components(vel_x, vel_y, vel_z),
-        };
+        match (time, position) {
+            (Some(time), Some(position)) => keys.push(Keyframe {
+                time,
+                position,
+                rotation,
+            }),
+            _ => return Err(Box::new(invalid)),
+        }
+    }
 
-        particles.push(particle);
+    // An empty track just stops the collider
+    let motion = KeyframeTrack::new(keys, looping).map(Motion::Keyframes);
+
+    let scenePtr = JUICE_SINGLETON.get_scene();
+    // Block while waiting for access to the mutex
+    let mut sceneLock = scenePtr.lock().expect("Could not lock scene (wtf?)");
+    let sceneObject = &mut *sceneLock;
+
+    sceneObject.setMotion(collider_id, motion)?;
+
+    Ok(0)
+}
+
+#[lua_function]
+fn stopColliderMotion(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect the arguments like this: collider ID
+    let collider_id = getColliderId(state, 1)?;
+
+    let scenePtr = JUICE_SINGLETON.get_scene();
+    // Block while waiting for access to the mutex
+    let mut sceneLock = scenePtr.lock().expect("Could not lock scene (wtf?)");
+    let sceneObject = &mut *sceneLock;
+
+    // The collider stays wherever the motion left it
+    sceneObject.setMotion(collider_id, None)?;
+
+    Ok(0)
+}
+
+#[lua_function]
+fn getColliderInfo(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect the arguments like this: collider ID
+    let collider_id = getColliderId(state, 1)?;
+
+    let scenePtr = JUICE_SINGLETON.get_scene();
+    // Block while waiting for access to the mutex
+    let mut sceneLock = scenePtr.lock().expect("Could not lock scene (wtf?)");
+    let sceneObject = &mut *sceneLock;
+
+    let collider = sceneObject.get(collider_id)?;
+    let (mins, maxs) = collider.bounds();
+
+    lua_createtable(state, 0, 9);
+    let typeName = collider.typeName();
+    lua_pushlstring(state, typeName.as_ptr() as *const _, typeName.len());
+    lua_setfield(state, -2, cstr!("type"));
+
+    pushVector(state, &collider.position());
+    lua_setfield(state, -2, cstr!("pos"));
+
+    // The rotation as it was set, without the alignment some colliders add for FleX
+    pushQuat(state, &collider.state().rotation);
+    lua_setfield(state, -2, cstr!("ang"));
+
+    pushVector(state, &mins);
+    lua_setfield(state, -2, cstr!("mins"));
+
+    pushVector(state, &maxs);
+    lua_setfield(state, -2, cstr!("maxs"));
+
+    lua_pushboolean(state, collider.isDynamic() as i32);
+    lua_setfield(state, -2, cstr!("dynamic"));
+
+    lua_pushboolean(state, collider.isEnabled() as i32);
+    lua_setfield(state, -2, cstr!("enabled"));
+
+    lua_pushnumber(state, collider.channels() as f64);
+    lua_setfield(state, -2, cstr!("channels"));
+
+    let parent = sceneObject
+        .objects
+        .get(collider_id)
+        .and_then(|record| record.attachment.as_ref())
+        .map(|attachment| attachment.parent);
+    if let Some(parent) = parent {
+        lua_pushnumber(state, parent.to_raw() as f64);
+        lua_setfield(state, -2, cstr!("parent"));
     }
 
-    for particle in particles {
-        particleObject.add_particle(particle);
+    Ok(1)
+}
+
+#[lua_function]
+fn listColliders(state: LuaState) -> Result<i32, std::io::Error> {
+    let scenePtr = JUICE_SINGLETON.get_scene();
+    // Block while waiting for access to the mutex
+    let sceneLock = scenePtr.lock().expect("Could not lock scene (wtf?)");
+    let sceneObject = &*sceneLock;
+
+    let ids = sceneObject.objects.handles();
+    lua_createtable(state, ids.len() as i32, 0);
+
+    for (i, id) in ids.iter().enumerate() {
+        lua_pushinteger(state, i as isize + 1);
+        lua_pushnumber(state, id.to_raw() as f64);
+        lua_settable(state, -3);
+    }
+
+    Ok(1)
+}
+
+#[lua_function]
+fn removeCollider(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    let collider_id = getColliderId(state, -1)?;
+
+    let scenePtr = JUICE_SINGLETON.get_scene();
+    // Block while waiting for access to the mutex
+    let mut sceneLock = scenePtr.lock().expect("Could not lock scene (wtf?)");
+    let sceneObject = &mut *sceneLock;
+
+    sceneObject.remove(collider_id)?;
+
+    Ok(0)
+}
+
+/// Reads a collider count from `index`, raising an error if it is negative
+fn checkCount(state: LuaState, index: i32) -> Result<usize, ArgError> {
+    let count = luaL_checkinteger(state, index);
+    if count < 0 {
+        return Err(ArgError {
+            index,
+            expected: "non-negative count",
+        });
+    }
+
+    Ok(count as usize)
+}
+
+#[lua_function]
+fn setColliderLimit(state: LuaState) -> Result<i32, ArgError> {
+    // We expect arguments like this: the maximum amount of colliders
+    let limit = checkCount(state, 1)?;
+
+    let scenePtr = JUICE_SINGLETON.get_scene();
+    // Block while waiting for access to the mutex
+    let mut sceneLock = scenePtr.lock().expect("Could not lock scene (wtf?)");
+    let sceneObject = &mut *sceneLock;
+    // Colliders above the new limit are kept, it only stops new ones from being added
+    sceneObject.limit = limit;
+
+    Ok(0)
+}
+
+#[lua_function]
+fn reserveColliders(state: LuaState) -> Result<i32, ArgError> {
+    // We expect arguments like this: the amount of colliders to make room for
+    let count = checkCount(state, 1)?;
+
+    unsafe {
+        JUICE_SINGLETON.reserveColliders(count);
     }
 
     Ok(0)
 }
 
+// Collider-specific related down here
+/// Raises an error unless both sizes of a capsule are positive, `first` is the argument index of the radius
+fn checkCapsuleSize(first: i32, radius: f32, halfheight: f32) -> Result<(), ArgError> {
+    for (index, size, expected) in [
+        (first, radius, "positive radius"),
+        (first + 1, halfheight, "positive half height"),
+    ] {
+        if size.is_nan() || size <= 0.0 {
+            return Err(ArgError { index, expected });
+        }
+    }
+
+    Ok(())
+}
+
+#[lua_function]
+fn spawnPlayerCollider(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: radius, half height, upright (all optional)
+    let radius = luaL_optnumber(state, 1, 12.0) as f32;
+    let halfheight = luaL_optnumber(state, 2, 10.0) as f32;
+    let upright = lua_toboolean(state, 3) != 0;
+    checkCapsuleSize(1, radius, halfheight)?;
+
+    let mut capsule = Capsule::new(radius, halfheight);
+    capsule.upright = upright;
+    let collider = Box::new(capsule);
+
+    let scenePtr = JUICE_SINGLETON.get_scene();
+    // Block while waiting for access to the mutex
+    let mut sceneLock = scenePtr.lock().expect("Could not lock scene (wtf?)");
+    let sceneObject = &mut *sceneLock;
+
+    let idx = sceneObject.add(collider)?;
+
+    // Finally, finished!!
+    // Return the ID of the collider
+    lua_pushnumber(state, idx.to_raw() as f64);
+    Ok(1)
+}
+
+#[lua_function]
+fn setCapsuleSize(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: collider ID, radius, half height
+    let collider_id = getColliderId(state, 1)?;
+    let radius = luaL_checknumber(state, 2) as f32;
+    let halfheight = luaL_checknumber(state, 3) as f32;
+    checkCapsuleSize(2, radius, halfheight)?;
+
+    let scenePtr = JUICE_SINGLETON.get_scene();
+    // Block while waiting for access to the mutex
+    let mut sceneLock = scenePtr.lock().expect("Could not lock scene (wtf?)");
+    let sceneObject = &mut *sceneLock;
+
+    let capsule = sceneObject
+        .get(collider_id)?
+        .as_any_mut()
+        .downcast_mut::<Capsule>()
+        .ok_or(SceneError::WrongType(collider_id.to_raw(), "capsule"))?;
+    capsule.setSize(radius, halfheight);
+
+    Ok(0)
+}
+
+#[lua_function]
+fn createBoxCollider(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: half extents (a Vector)
+    let halfExtents = checkVector(state, 1)?;
+
+    let scenePtr = JUICE_SINGLETON.get_scene();
+    // Block while waiting for access to the mutex
+    let mut sceneLock = scenePtr.lock().expect("Could not lock scene (wtf?)");
+    let sceneObject = &mut *sceneLock;
+
+    let idx = sceneObject.add(Box::new(Cuboid::new(halfExtents)))?;
+
+    // Return the ID of the collider
+    lua_pushnumber(state, idx.to_raw() as f64);
+    Ok(1)
+}
+
+/// Reads a compound child table at the top of the stack, `entry` is its key in the first argument and only used for errors
+/// Children look like `{type = "capsule", radius, halfHeight, upright, pos, ang}` or `{type = "box", extents, pos, ang}`
+fn readCompoundChild(
+    state: LuaState,
+    entry: usize,
+) -> Result<(Box<dyn Collider>, Vec3, Quat), EntryError> {
+    lua_getfield(state, -1, cstr!("type"));
+    let kind = if lua_type(state, -1) == LUA_TSTRING {
+        rstr!(lua_tolstring(state, -1, std::ptr::null_mut())).to_string()
+    } else {
+        String::new()
+    };
+    lua_pop(state, 1);
+
+    let child: Box<dyn Collider> = match kind.as_str() {
+        "capsule" => {
+            let radius = tableNumber(state, -1, cstr!("radius")).unwrap_or(12.0);
+            let halfheight = tableNumber(state, -1, cstr!("halfHeight")).unwrap_or(10.0);
+
+            lua_getfield(state, -1, cstr!("upright"));
+            let upright = lua_toboolean(state, -1) != 0;
+            lua_pop(state, 1);
+
+            let mut capsule = Capsule::new(radius, halfheight);
+            capsule.upright = upright;
+            Box::new(capsule)
+        }
+        "box" => {
+            lua_getfield(state, -1, cstr!("extents"));
+            let extents = readVector(state, -1);
+            lua_pop(state, 1);
+
+            Box::new(Cuboid::new(extents.ok_or(EntryError {
+                index: 1,
+                entry,
+                expected: "box child with an extents Vector",
+            })?))
+        }
+        _ => {
+            return Err(EntryError {
+                index: 1,
+                entry,
+                expected: "child with a type of \"capsule\" or \"box\"",
+            })
+        }
+    };
+
+    // Children without an offset or rotation sit right on the compound
+    lua_getfield(state, -1, cstr!("pos"));
+    let offset = readVector(state, -1).unwrap_or(Vec3::new());
+    lua_pop(state, 1);
+
+    lua_getfield(state, -1, cstr!("ang"));
+    let rotation = readRotation(state, -1).unwrap_or(Quat::identity());
+    lua_pop(state, 1);
+
+    Ok((child, offset, rotation))
+}
+
+#[lua_function]
+fn createCompoundCollider(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: table of child shapes (see readCompoundChild)
+    if lua_type(state, 1) != LUA_TTABLE {
+        return Err(Box::new(ArgError {
+            index: 1,
+            expected: "table of child shapes",
+        }));
+    }
+    lua_settop(state, 1);
+
+    let mut compound = Compound::new();
+
+    let tableLength = lua_objlen(state, -1);
+    for i in 0..tableLength {
+        // Lua indices go 1, 2, 3, ...
+        let real_index = i + 1;
+        lua_pushnumber(state, real_index as f64);
+        lua_gettable(state, -2);
+
+        if lua_type(state, -1) != LUA_TTABLE {
+            return Err(Box::new(EntryError {
+                index: 1,
+                entry: real_index as usize,
+                expected: "child shape table",
+            }));
+        }
+
+        let (child, offset, rotation) = readCompoundChild(state, real_index as usize)?;
+        compound.addChild(child, offset, rotation);
+
+        // Pop the child table off
+        lua_pop(state, 1);
+    }
+
+    let scenePtr = JUICE_SINGLETON.get_scene();
+    // Block while waiting for access to the mutex
+    let mut sceneLock = scenePtr.lock().expect("Could not lock scene (wtf?)");
+    let sceneObject = &mut *sceneLock;
+
+    let idx = sceneObject.add(Box::new(compound))?;
+
+    // Return the ID of the collider
+    lua_pushnumber(state, idx.to_raw() as f64);
+    Ok(1)
+}
+
+#[lua_function]
+fn loadMap(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: path to the .bsp (from the game's root, like "garrysmod/maps/gm_construct.bsp"),
+    // content mask (optional, solid by default), displacements (optional, true by default)
+    let path = rstr!(luaL_checklstring(state, 1, std::ptr::null_mut())).to_string();
+    let path = loader::gamePath(&path).ok_or(ArgError {
+        index: 1,
+        expected: "path inside the game's directory",
+    })?;
+    let options = BspOptions {
+        contents: luaL_optinteger(state, 2, bsp::CONTENTS_SOLID as _) as i32,
+        displacements: lua_type(state, 3) <= LUA_TNIL || lua_toboolean(state, 3) != 0,
+    };
+
+    let geometry = bsp::parse(&std::fs::read(&path)?, &options)?;
+    let displacementMesh = geometry.displacementMesh();
+
+    let scenePtr = JUICE_SINGLETON.get_scene();
+    // Block while waiting for access to the mutex
+    let mut sceneLock = scenePtr.lock().expect("Could not lock scene (wtf?)");
+    let sceneObject = &mut *sceneLock;
+
+    // Either the whole map fits, or none of it is added
+    // Every brush is a collider of its own, so big maps need a higher limit than the default
+    let count = geometry.brushes.len() + displacementMesh.is_some() as usize;
+    if sceneObject.len() + count > sceneObject.limit {
+        return Err(Box::new(SceneError::NoRoom(count, sceneObject.limit)));
+    }
+
+    // Return the IDs of every collider that was created
+    lua_createtable(state, count as i32, 0);
+    let mut pushed = 0;
+
+    for brush in geometry.brushes {
+        let idx = sceneObject.add(Box::new(Convex::new(
+            brush.planes,
+            brush.lower,
+            brush.upper,
+        )))?;
+
+        pushed += 1;
+        lua_pushnumber(state, idx.to_raw() as f64);
+        lua_rawseti(state, -2, pushed);
+    }
+
+    if let Some((vertices, indices)) = displacementMesh {
+        let (lower_bound, upper_bound) = SharedMesh::compute_bounds(&vertices);
+        let mesh = unsafe {
+            SharedMesh::new(
+                JUICE_SINGLETON.get_lib(),
+                &vertices,
+                &indices,
+                lower_bound,
+                upper_bound,
+            )
+        };
+
+        let idx = sceneObject.add(Box::new(Mesh::new(Arc::new(mesh))))?;
+
+        pushed += 1;
+        lua_pushnumber(state, idx.to_raw() as f64);
+        lua_rawseti(state, -2, pushed);
+    }
+
+    Ok(1)
+}
+
+/// Reads a mesh options table at the index, anything missing (or the whole table) falls back to the defaults
+/// The table looks like `{scale = number or Vector, pos = Vector, ang = Angle, weld = tolerance, flip = bool}`
+/// Adding `triangles = count` and/or `tolerance = units` simplifies the mesh before it is uploaded
+/// `repair`, `container` and `doubleSided` (all bools) fix up which way the triangles face
+fn readMeshOptions(state: LuaState, index: i32) -> MeshOptions {
+    let mut options = MeshOptions::default();
+    if lua_type(state, index) != LUA_TTABLE {
+        return options;
+    }
+
+    lua_getfield(state, index, cstr!("scale"));
+    if lua_type(state, -1) == LUA_TNUMBER {
+        let scale = lua_tonumber(state, -1) as f32;
+        options.scale = Vec3::components(scale, scale, scale);
+    } else if let Some(scale) = readVector(state, -1) {
+        options.scale = scale;
+    }
+    lua_pop(state, 1);
+
+    lua_getfield(state, index, cstr!("pos"));
+    options.offset = readVector(state, -1).unwrap_or(options.offset);
+    lua_pop(state, 1);
+
+    lua_getfield(state, index, cstr!("ang"));
+    options.rotation = readRotation(state, -1).unwrap_or(options.rotation);
+    lua_pop(state, 1);
+
+    options.weld = tableNumber(state, index, cstr!("weld"));
+    options.targetTriangles =
+        tableNumber(state, index, cstr!("triangles")).map(|count| count.max(0.0) as usize);
+    options.tolerance = tableNumber(state, index, cstr!("tolerance"));
+
+    let flag = |name| {
+        lua_getfield(state, index, name);
+        let set = lua_toboolean(state, -1) != 0;
+        lua_pop(state, 1);
+        set
+    };
+
+    options.flip = flag(cstr!("flip"));
+    options.repairWinding = flag(cstr!("repair"));
+    options.container = flag(cstr!("container"));
+    options.doubleSided = flag(cstr!("doubleSided"));
+
+    options
+}
+
+/// Prepares a mesh with the options, then uploads it and adds it to the scene as a `Mesh` collider
+/// Pushes the ID of the collider, along with the triangle counts before and after preparing
+fn addPreparedMesh(
+    state: LuaState,
+    mut mesh: TriangleMesh,
+    options: &MeshOptions,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let trianglesBefore = mesh.triangleCount();
+    mesh.prepare(options);
+
+    if mesh.triangleCount() == 0 {
+        printgm!(
+            state,
+            "The mesh has no triangles left, not creating a collider"
+        );
+        return Ok(0);
+    }
+
+    let (lower_bound, upper_bound) = mesh.bounds();
+    let sharedMesh = unsafe {
+        SharedMesh::new(
+            JUICE_SINGLETON.get_lib(),
+            &mesh.flexVertices(),
+            &mesh.flexIndices(),
+            lower_bound,
+            upper_bound,
+        )
+    };
+
+    let scenePtr = JUICE_SINGLETON.get_scene();
+    // Block while waiting for access to the mutex
+    let mut sceneLock = scenePtr.lock().expect("Could not lock scene (wtf?)");
+    let sceneObject = &mut *sceneLock;
+
+    let idx = sceneObject.add(Box::new(Mesh::new(Arc::new(sharedMesh))))?;
+
+    // Return the ID of the collider, and how much simplifying (if any) saved
+    lua_pushnumber(state, idx.to_raw() as f64);
+    lua_pushnumber(state, trianglesBefore as f64);
+    lua_pushnumber(state, mesh.triangleCount() as f64);
+    Ok(3)
+}
+
+#[lua_function]
+fn loadMeshCollider(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: path to an .obj, .stl or .ply (from the game's root), options table (optional)
+    let path = rstr!(luaL_checklstring(state, 1, std::ptr::null_mut())).to_string();
+    let options = readMeshOptions(state, 2);
+    let fullPath = loader::gamePath(&path).ok_or(ArgError {
+        index: 1,
+        expected: "path inside the game's directory",
+    })?;
+
+    let mesh = loadMesh(&fullPath)?;
+    addPreparedMesh(state, mesh, &options)
+}
+
+/// Reads a table of plain numbers at the index, like a list of heights
+fn readNumbers(state: LuaState, index: i32) -> Result<Vec<f32>, ArgError> {
+    if lua_type(state, index) != LUA_TTABLE {
+        return Err(ArgError {
+            index,
+            expected: "table of numbers",
+        });
+    }
+
+    let tableLength = lua_objlen(state, index);
+    let mut numbers: Vec<f32> = Vec::with_capacity(tableLength as usize);
+
+    for i in 0..tableLength {
+        // Lua indices go 1, 2, 3, ...
+        lua_rawgeti(state, index, (i + 1) as _);
+        if lua_type(state, -1) != LUA_TNUMBER {
+            return Err(ArgError {
+                index,
+                expected: "table of numbers",
+            });
+        }
+
+        numbers.push(lua_tonumber(state, -1) as f32);
+        lua_pop(state, 1);
+    }
+
+    Ok(numbers)
+}
+
+#[lua_function]
+fn createHeightfield(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: columns, rows, spacing, heights (row by row), origin (a Vector, optional)
+    let columns = luaL_checkinteger(state, 1).max(0) as usize;
+    let rows = luaL_checkinteger(state, 2).max(0) as usize;
+    let spacing = luaL_checknumber(state, 3) as f32;
+    let heights = readNumbers(state, 4)?;
+    let origin = readVector(state, 5).unwrap_or(Vec3::new());
+
+    let mut heightfield = Heightfield::new(columns, rows, spacing, heights).ok_or(ArgError {
+        index: 4,
+        expected: "columns * rows heights, in a grid of at least 2x2",
+    })?;
+    heightfield.setPosition(origin);
+
+    let scenePtr = JUICE_SINGLETON.get_scene();
+    // Block while waiting for access to the mutex
+    let mut sceneLock = scenePtr.lock().expect("Could not lock scene (wtf?)");
+    let sceneObject = &mut *sceneLock;
+
+    let idx = sceneObject.add(Box::new(heightfield))?;
+
+    // Return the ID of the collider
+    lua_pushnumber(state, idx.to_raw() as f64);
+    Ok(1)
+}
+
+#[lua_function]
+fn setHeightfieldHeights(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: collider ID, first column, first row, width, heights (row by row)
+    // Columns and rows start at 1, like everything else in Lua
+    let collider_id = getColliderId(state, 1)?;
+    let column = (luaL_checkinteger(state, 2) - 1).max(0) as usize;
+    let row = (luaL_checkinteger(state, 3) - 1).max(0) as usize;
+    let width = luaL_checkinteger(state, 4).max(0) as usize;
+    let heights = readNumbers(state, 5)?;
+
+    let scenePtr = JUICE_SINGLETON.get_scene();
+    // Block while waiting for access to the mutex
+    let mut sceneLock = scenePtr.lock().expect("Could not lock scene (wtf?)");
+    let sceneObject = &mut *sceneLock;
+
+    let heightfield = sceneObject
+        .get(collider_id)?
+        .as_any_mut()
+        .downcast_mut::<Heightfield>()
+        .ok_or(SceneError::WrongType(collider_id.to_raw(), "heightfield"))?;
+
+    if !heightfield.setHeights(column, row, width, &heights) {
+        return Err(Box::new(ArgError {
+            index: 5,
+            expected: "a rectangle of heights inside the heightfield",
+        }));
+    }
+
+    Ok(0)
+}
+
+/// Queues an event for the solver to invoke on its next tick
+fn queueEvent(event: Box<dyn Event>) {
+    let eventPtr = JUICE_SINGLETON.get_event_queue();
+    // Block while waiting for access to the mutex
+    let mut eventLock = eventPtr.lock().expect("Could not lock event queue (wtf?)");
+    let eventObject = &mut *eventLock;
+    eventObject.add_event(event);
+}
+
+/// Sorts two corners of a box into its lowest and highest corner, so Lua can pass them either way around
+fn orderedCorners(first: Vec3, second: Vec3) -> (Vec3, Vec3) {
+    (
+        Vec3::components(
+            first.x.min(second.x),
+            first.y.min(second.y),
+            first.z.min(second.z),
+        ),
+        Vec3::components(
+            first.x.max(second.x),
+            first.y.max(second.y),
+            first.z.max(second.z),
+        ),
+    )
+}
+
+/// Reads a region table, either a sphere (`{pos = Vector, radius = number}`), a box (`{mins = Vector, maxs = Vector}`)
+/// or a capsule (`{pos = Vector, endpos = Vector, radius = number}`)
+fn readRegion(state: LuaState, index: i32) -> Result<Region, ArgError> {
+    let error = ArgError {
+        index,
+        expected: "region ({pos, radius}, {mins, maxs} or {pos, endpos, radius})",
+    };
+
+    if lua_type(state, index) != LUA_TTABLE {
+        return Err(error);
+    }
+
+    if let Some(radius) = tableNumber(state, index, cstr!("radius")) {
+        lua_getfield(state, index, cstr!("pos"));
+        let start = readVector(state, -1);
+        lua_getfield(state, index, cstr!("endpos"));
+        let end = readVector(state, -1);
+        lua_pop(state, 2);
+
+        return match (start, end) {
+            (Some(start), Some(end)) => Ok(Region::Capsule { start, end, radius }),
+            (Some(center), None) => Ok(Region::Sphere { center, radius }),
+            _ => Err(error),
+        };
+    }
+
+    lua_getfield(state, index, cstr!("mins"));
+    let mins = readVector(state, -1);
+    lua_getfield(state, index, cstr!("maxs"));
+    let maxs = readVector(state, -1);
+    lua_pop(state, 2);
+
+    match (mins, maxs) {
+        (Some(mins), Some(maxs)) => {
+            let (lower, upper) = orderedCorners(mins, maxs);
+            Ok(Region::Box { lower, upper })
+        }
+        _ => Err(error),
+    }
+}
+
+/// Reads the arguments of `SetParticles` starting at `first`: position (a Vector)
+fn readSetParticles(state: LuaState, first: i32) -> Result<Box<dyn Event>, ArgError> {
+    let position = checkVector(state, first)?;
+
+    Ok(Box::new(SetParticleEvent {
+        position: Vec4::from(&position),
+    }))
+}
+
+/// Reads the arguments of `Explode` starting at `first`: center, radius, strength, falloff (optional, 1 fades linearly)
+fn readExplosion(state: LuaState, first: i32) -> Result<Box<dyn Event>, ArgError> {
+    let center = checkVector(state, first)?;
+    let radius = luaL_checknumber(state, first + 1) as f32;
+    if radius <= 0.0 {
+        return Err(ArgError {
+            index: first + 1,
+            expected: "positive radius",
+        });
+    }
+
+    let strength = luaL_checknumber(state, first + 2) as f32;
+    let falloff = luaL_optnumber(state, first + 3, 1.0) as f32;
+
+    Ok(Box::new(ExplosionEvent {
+        center,
+        radius,
+        strength,
+        falloff: falloff.max(0.0),
+    }))
+}
+
+/// Reads the arguments of `ApplyImpulse` starting at `first`: region, impulse (a Vector added to the velocity)
+fn readImpulse(state: LuaState, first: i32) -> Result<Box<dyn Event>, ArgError> {
+    let region = readRegion(state, first)?;
+    let impulse = checkVector(state, first + 1)?;
+
+    Ok(Box::new(ImpulseEvent { region, impulse }))
+}
+
+/// Reads the arguments of `Vortex` starting at `first`: center, axis, radius, strength, pull (optional)
+fn readVortex(state: LuaState, first: i32) -> Result<Box<dyn Event>, ArgError> {
+    let center = checkVector(state, first)?;
+    let axis = checkVector(state, first + 1)?;
+    let radius = luaL_checknumber(state, first + 2) as f32;
+    if radius <= 0.0 {
+        return Err(ArgError {
+            index: first + 2,
+            expected: "positive radius",
+        });
+    }
+
+    let strength = luaL_checknumber(state, first + 3) as f32;
+    let pull = luaL_optnumber(state, first + 4, 0.0) as f32;
+
+    Ok(Box::new(VortexEvent {
+        center,
+        axis,
+        radius,
+        strength,
+        pull,
+    }))
+}
+
+/// Reads the arguments of `SetRegionVelocity` starting at `first`: region, velocity
+fn readSetVelocity(state: LuaState, first: i32) -> Result<Box<dyn Event>, ArgError> {
+    let region = readRegion(state, first)?;
+    let velocity = checkVector(state, first + 1)?;
+
+    Ok(Box::new(VelocityEvent {
+        region,
+        change: VelocityChange::Set(velocity),
+    }))
+}
+
+/// Reads the arguments of `ScaleRegionVelocity` starting at `first`: region, factor
+fn readScaleVelocity(state: LuaState, first: i32) -> Result<Box<dyn Event>, ArgError> {
+    let region = readRegion(state, first)?;
+    let factor = luaL_checknumber(state, first + 1) as f32;
+
+    Ok(Box::new(VelocityEvent {
+        region,
+        change: VelocityChange::Scale(factor),
+    }))
+}
+
+/// Reads the arguments of `TeleportRegion` starting at `first`: source mins, source maxs, destination mins, destination maxs
+fn readTeleport(state: LuaState, first: i32) -> Result<Box<dyn Event>, ArgError> {
+    let (fromLower, fromUpper) =
+        orderedCorners(checkVector(state, first)?, checkVector(state, first + 1)?);
+    let (toLower, toUpper) = orderedCorners(
+        checkVector(state, first + 2)?,
+        checkVector(state, first + 3)?,
+    );
+
+    Ok(Box::new(TeleportEvent {
+        fromLower,
+        fromUpper,
+        toLower,
+        toUpper,
+    }))
+}
+
+/// Reads the arguments of an event, starting at the given index
+type EventReader = fn(LuaState, i32) -> Result<Box<dyn Event>, ArgError>;
+
+/// Finds the reader of an event by the name of the Lua function which queues it
+fn eventReader(name: &str) -> Option<EventReader> {
+    Some(match name {
+        "SetParticles" => readSetParticles,
+        "Explode" => readExplosion,
+        "ApplyImpulse" => readImpulse,
+        "Vortex" => readVortex,
+        "SetRegionVelocity" => readSetVelocity,
+        "ScaleRegionVelocity" => readScaleVelocity,
+        "TeleportRegion" => readTeleport,
+        _ => return None,
+    })
+}
+
+#[lua_function]
+fn setParticles(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    queueEvent(readSetParticles(state, 1)?);
+    Ok(0)
+}
+
+#[lua_function]
+fn explode(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    queueEvent(readExplosion(state, 1)?);
+    Ok(0)
+}
+
+#[lua_function]
+fn applyImpulse(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    queueEvent(readImpulse(state, 1)?);
+    Ok(0)
+}
+
+#[lua_function]
+fn vortex(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    queueEvent(readVortex(state, 1)?);
+    Ok(0)
+}
+
+#[lua_function]
+fn setRegionVelocity(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    queueEvent(readSetVelocity(state, 1)?);
+    Ok(0)
+}
+
+#[lua_function]
+fn scaleRegionVelocity(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    queueEvent(readScaleVelocity(state, 1)?);
+    Ok(0)
+}
+
+#[lua_function]
+fn teleportRegion(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    queueEvent(readTeleport(state, 1)?);
+    Ok(0)
+}
+
+/// Reads a schedule table, measured from the current tick and time of the event queue
+/// Times are in simulated seconds, see `getSimulationTime`
+///
+/// Fields (all optional): `ticks` (measure in solver ticks rather than seconds), `delay` (until the first invocation),
+/// `at` (when the first invocation happens, instead of `delay`), `period` (repeats the event, at most once a tick),
+/// `count` (the most invocations) and `duration` (how long it keeps repeating, from now)
+fn readSchedule(state: LuaState, index: i32, tick: u64, time: f64) -> Result<Schedule, ArgError> {
+    if lua_type(state, index) != LUA_TTABLE {
+        return Err(ArgError {
+            index,
+            expected: "schedule table",
+        });
+    }
+
+    lua_getfield(state, index, cstr!("ticks"));
+    let clock = if lua_toboolean(state, -1) != 0 {
+        Clock::Ticks
+    } else {
+        Clock::Seconds
+    };
+    lua_pop(state, 1);
+
+    let now = match clock {
+        Clock::Ticks => tick as f64,
+        Clock::Seconds => time,
+    };
+
+    let field = |key| tableNumber(state, index, key).map(|value| value as f64);
+
+    let next = match field(cstr!("at")) {
+        Some(at) => at,
+        None => now + field(cstr!("delay")).unwrap_or(0.0).max(0.0),
+    };
+
+    let period = field(cstr!("period"));
+    if period.is_some_and(|period| period <= 0.0) {
+        return Err(ArgError {
+            index,
+            expected: "schedule with a positive period",
+        });
+    }
+
+    // Events are invoked at most once per tick, so shorter periods can't be kept anyway
+    let shortest = match clock {
+        Clock::Ticks => 1.0,
+        Clock::Seconds => TIME_STEP as f64,
+    };
+
+    Ok(Schedule {
+        clock,
+        next,
+        period: period.map(|period| period.max(shortest)),
+        remaining: field(cstr!("count")).map(|count| count.max(1.0) as u32),
+        end: field(cstr!("duration")).map(|duration| now + duration),
+    })
+}
+
+#[lua_function]
+fn scheduleEvent(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: schedule table, event name (like "Explode"), then the arguments of that event
+    let name = rstr!(luaL_checklstring(state, 2, std::ptr::null_mut())).to_string();
+    let reader = eventReader(&name).ok_or(ArgError {
+        index: 2,
+        expected: "event name",
+    })?;
+    let event = reader(state, 3)?;
+
+    let eventPtr = JUICE_SINGLETON.get_event_queue();
+    // Block while waiting for access to the mutex
+    let mut eventLock = eventPtr.lock().expect("Could not lock event queue (wtf?)");
+    let eventObject = &mut *eventLock;
+
+    let schedule = readSchedule(state, 1, eventObject.tick, eventObject.time)?;
+    let id = eventObject.schedule(event, schedule);
+
+    lua_pushnumber(state, id.to_raw() as f64);
+    Ok(1)
+}
+
+#[lua_function]
+fn cancelEvent(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: the handle returned by `ScheduleEvent`
+    let cancelled = match readHandle(state, 1) {
+        Some(id) => {
+            let eventPtr = JUICE_SINGLETON.get_event_queue();
+            // Block while waiting for access to the mutex
+            let mut eventLock = eventPtr.lock().expect("Could not lock event queue (wtf?)");
+            eventLock.cancel(id)
+        }
+        None => false,
+    };
+
+    lua_pushboolean(state, cancelled as i32);
+    Ok(1)
+}
+
+#[lua_function]
+fn getSimulationTime(state: LuaState) -> Result<i32, std::io::Error> {
+    // Returns the simulated seconds, then the ticks so far
+    // Every tick (10ms of real time) simulates `TIME_STEP` seconds, so simulated time runs about 8 times faster
+    let eventPtr = JUICE_SINGLETON.get_event_queue();
+    // Block while waiting for access to the mutex
+    let eventLock = eventPtr.lock().expect("Could not lock event queue (wtf?)");
+
+    lua_pushnumber(state, eventLock.time);
+    lua_pushnumber(state, eventLock.tick as f64);
+    Ok(2)
+}
+
+/// Reads a force field ID from the stack, IDs are handed to Lua as plain numbers
+fn getFieldId(state: LuaState, index: i32) -> Result<Handle, FieldError> {
+    readHandle(state, index).ok_or(FieldError::InvalidId(lua_tonumber(state, index)))
+}
+
+/// Reads a force field options table
+///
+/// Fields: `type` ("directional", "radial", "vortex" or "noise"), `strength` (units per simulated second squared),
+/// `falloff` ("none", "linear", "quadratic" or "smooth"), `dir` (directional), `axis` (vortex), `scale` and `speed`
+/// (noise, in swirls per simulated second)
+fn readFieldOptions(state: LuaState, index: i32) -> Result<(FieldKind, f32, Falloff), ArgError> {
+    if lua_type(state, index) != LUA_TTABLE {
+        return Err(ArgError {
+            index,
+            expected: "force field options table",
+        });
+    }
+
+    let vector = |key| {
+        lua_getfield(state, index, key);
+        let vector = readVector(state, -1);
+        lua_pop(state, 1);
+        vector
+    };
+
+    let kind = match tableString(state, index, cstr!("type")).as_deref() {
+        Some("directional") => FieldKind::Directional(vector(cstr!("dir")).ok_or(ArgError {
+            index,
+            expected: "directional force field with a dir Vector",
+        })?),
+        Some("radial") => FieldKind::Radial,
+        Some("vortex") => {
+            FieldKind::Vortex(vector(cstr!("axis")).unwrap_or(Vec3::components(0.0, 0.0, 1.0)))
+        }
+        Some("noise") => FieldKind::Noise {
+            scale: tableNumber(state, index, cstr!("scale")).unwrap_or(64.0),
+            speed: tableNumber(state, index, cstr!("speed")).unwrap_or(1.0),
+        },
+        _ => {
+            return Err(ArgError {
+                index,
+                expected: "force field type (directional, radial, vortex or noise)",
+            })
+        }
+    };
+
+    let falloff = match tableString(state, index, cstr!("falloff")).as_deref() {
+        None | Some("none") => Falloff::None,
+        Some("linear") => Falloff::Linear,
+        Some("quadratic") => Falloff::Quadratic,
+        Some("smooth") => Falloff::Smooth,
+        _ => {
+            return Err(ArgError {
+                index,
+                expected: "falloff (none, linear, quadratic or smooth)",
+            })
+        }
+    };
+
+    let strength = tableNumber(state, index, cstr!("strength")).unwrap_or(1.0);
+
+    Ok((kind, strength, falloff))
+}
+
+#[lua_function]
+fn createForceField(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: region, options
+    let region = readRegion(state, 1)?;
+    let (kind, strength, falloff) = readFieldOptions(state, 2)?;
+
+    let fieldsPtr = JUICE_SINGLETON.get_fields();
+    // Block while waiting for access to the mutex
+    let mut fieldsLock = fieldsPtr.lock().expect("Could not lock fields (wtf?)");
+    let id = fieldsLock.add(ForceField::new(region, kind, strength, falloff));
+
+    lua_pushnumber(state, id.to_raw() as f64);
+    Ok(1)
+}
+
+#[lua_function]
+fn setForceFieldEnabled(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: id, enabled
+    let field_id = getFieldId(state, 1)?;
+    let enabled = lua_toboolean(state, 2) != 0;
+
+    let fieldsPtr = JUICE_SINGLETON.get_fields();
+    // Block while waiting for access to the mutex
+    let mut fieldsLock = fieldsPtr.lock().expect("Could not lock fields (wtf?)");
+    fieldsLock.get(field_id)?.enabled = enabled;
+
+    Ok(0)
+}
+
+#[lua_function]
+fn setForceFieldStrength(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: id, strength
+    let field_id = getFieldId(state, 1)?;
+    let strength = luaL_checknumber(state, 2) as f32;
+
+    let fieldsPtr = JUICE_SINGLETON.get_fields();
+    // Block while waiting for access to the mutex
+    let mut fieldsLock = fieldsPtr.lock().expect("Could not lock fields (wtf?)");
+    fieldsLock.get(field_id)?.strength = strength;
+
+    Ok(0)
+}
+
+#[lua_function]
+fn setForceFieldRegion(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: id, region
+    let field_id = getFieldId(state, 1)?;
+    let region = readRegion(state, 2)?;
+
+    let fieldsPtr = JUICE_SINGLETON.get_fields();
+    // Block while waiting for access to the mutex
+    let mut fieldsLock = fieldsPtr.lock().expect("Could not lock fields (wtf?)");
+    fieldsLock.get(field_id)?.region = region;
+
+    Ok(0)
+}
+
+#[lua_function]
+fn removeForceField(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: id
+    let field_id = getFieldId(state, 1)?;
+
+    let fieldsPtr = JUICE_SINGLETON.get_fields();
+    // Block while waiting for access to the mutex
+    let mut fieldsLock = fieldsPtr.lock().expect("Could not lock fields (wtf?)");
+    fieldsLock.remove(field_id)?;
+
+    Ok(0)
+}
+
+/// Reads an emitter ID from the stack, IDs are handed to Lua as plain numbers
+fn getEmitterId(state: LuaState, index: i32) -> Result<Handle, EmitterError> {
+    readHandle(state, index).ok_or(EmitterError::InvalidId(lua_tonumber(state, index)))
+}
+
+/// Makes sure a collider exists before something is attached to it
+fn checkCollider(id: Handle) -> Result<Handle, SceneError> {
+    let scenePtr = JUICE_SINGLETON.get_scene();
+    // Block while waiting for access to the mutex
+    let sceneLock = scenePtr.lock().expect("Could not lock scene (wtf?)");
+
+    if sceneLock.isValid(id) {
+        Ok(id)
+    } else {
+        Err(SceneError::StaleId(id.to_raw()))
+    }
+}
+
+/// Keeps an emission rate below a full particle buffer every tick, anything more could never be spawned
+fn clampRate(rate: f32) -> f32 {
+    rate.clamp(0.0, MAX_PARTICLES as f32 / TIME_STEP)
+}
+
+/// Reads an emitter options table into a new emitter at `position`
+///
+/// Fields (all optional): `shape` ("point", "disc", "box" or "sphere"), `radius` (disc and sphere), `size` (a Vector,
+/// box), `rate` (particles per simulated second), `burst` (particles at once, instead of `rate`), `interval`
+/// (simulated seconds between bursts, only one burst without it), `vel`, `spread` (degrees), `jitter`, `group` and
+/// `parent` (a collider ID)
+/// Simulated seconds pass about 8 times faster than real ones, see `getSimulationTime`
+fn readEmitter(
+    state: LuaState,
+    index: i32,
+    position: Vec3,
+) -> Result<Emitter, Box<dyn std::error::Error>> {
+    if lua_type(state, index) != LUA_TTABLE {
+        return Err(Box::new(ArgError {
+            index,
+            expected: "emitter options table",
+        }));
+    }
+
+    let radius = tableNumber(state, index, cstr!("radius")).unwrap_or(0.0);
+    lua_getfield(state, index, cstr!("size"));
+    let size = readVector(state, -1).unwrap_or(Vec3::new());
+    lua_pop(state, 1);
+
+    let shape = match tableString(state, index, cstr!("shape")).as_deref() {
+        None | Some("point") => EmitterShape::Point,
+        Some("disc") => EmitterShape::Disc(radius),
+        Some("box") => EmitterShape::Box(size.scale(0.5)),
+        Some("sphere") => EmitterShape::Sphere(radius),
+        _ => {
+            return Err(Box::new(ArgError {
+                index,
+                expected: "emitter shape (point, disc, box or sphere)",
+            }))
+        }
+    };
+
+    let mode = match tableNumber(state, index, cstr!("burst")) {
+        Some(count) => EmitterMode::Burst {
+            count: count.clamp(0.0, MAX_PARTICLES as f32) as u32,
+            interval: tableNumber(state, index, cstr!("interval")),
+        },
+        None => EmitterMode::Continuous(clampRate(
+            tableNumber(state, index, cstr!("rate")).unwrap_or(0.0),
+        )),
+    };
+
+    let mut emitter = Emitter::new(position, shape, mode);
+
+    lua_getfield(state, index, cstr!("vel"));
+    emitter.velocity = readVector(state, -1).unwrap_or(Vec3::new());
+    lua_pop(state, 1);
+
+    emitter.spread = tableNumber(state, index, cstr!("spread")).unwrap_or(0.0);
+    emitter.jitter = tableNumber(state, index, cstr!("jitter")).unwrap_or(0.0);
+    let group = tableNumber(state, index, cstr!("group")).unwrap_or(0.0);
+    emitter.group = checkGroup(index, group as isize)?;
+
+    lua_getfield(state, index, cstr!("parent"));
+    let parent = if lua_type(state, -1) <= LUA_TNIL {
+        Ok(None)
+    } else {
+        getColliderId(state, -1).map(Some)
+    };
+    lua_pop(state, 1);
+
+    emitter.parent = match parent? {
+        Some(parent) => Some(checkCollider(parent)?),
+        None => None,
+    };
+
+    Ok(emitter)
+}
+
+#[lua_function]
+fn createEmitter(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: position (relative to the parent, if there is one), options
+    let position = checkVector(state, 1)?;
+    let emitter = readEmitter(state, 2, position)?;
+
+    let emittersPtr = JUICE_SINGLETON.get_emitters();
+    // Block while waiting for access to the mutex
+    let mut emittersLock = emittersPtr.lock().expect("Could not lock emitters (wtf?)");
+    let id = emittersLock.add(emitter);
+
+    lua_pushnumber(state, id.to_raw() as f64);
+    Ok(1)
+}
+
+#[lua_function]
+fn setEmitterPos(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: id, position
+    let emitter_id = getEmitterId(state, 1)?;
+    let position = checkVector(state, 2)?;
+
+    let emittersPtr = JUICE_SINGLETON.get_emitters();
+    // Block while waiting for access to the mutex
+    let mut emittersLock = emittersPtr.lock().expect("Could not lock emitters (wtf?)");
+    emittersLock.get(emitter_id)?.position = position;
+
+    Ok(0)
+}
+
+#[lua_function]
+fn setEmitterVelocity(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: id, velocity
+    let emitter_id = getEmitterId(state, 1)?;
+    let velocity = checkVector(state, 2)?;
+
+    let emittersPtr = JUICE_SINGLETON.get_emitters();
+    // Block while waiting for access to the mutex
+    let mut emittersLock = emittersPtr.lock().expect("Could not lock emitters (wtf?)");
+    emittersLock.get(emitter_id)?.velocity = velocity;
+
+    Ok(0)
+}
+
+#[lua_function]
+fn setEmitterRate(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: id, particles per simulated second (this also turns a burst emitter into a
+    // steady one)
+    let emitter_id = getEmitterId(state, 1)?;
+    let rate = clampRate(luaL_checknumber(state, 2) as f32);
+
+    let emittersPtr = JUICE_SINGLETON.get_emitters();
+    // Block while waiting for access to the mutex
+    let mut emittersLock = emittersPtr.lock().expect("Could not lock emitters (wtf?)");
+    emittersLock.get(emitter_id)?.mode = EmitterMode::Continuous(rate);
+
+    Ok(0)
+}
+
+#[lua_function]
+fn setEmitterEnabled(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: id, enabled
+    // Enabling a single burst emitter again fires another burst
+    let emitter_id = getEmitterId(state, 1)?;
+    let enabled = lua_toboolean(state, 2) != 0;
+
+    let emittersPtr = JUICE_SINGLETON.get_emitters();
+    // Block while waiting for access to the mutex
+    let mut emittersLock = emittersPtr.lock().expect("Could not lock emitters (wtf?)");
+    emittersLock.get(emitter_id)?.enabled = enabled;
+
+    Ok(0)
+}
+
+#[lua_function]
+fn setEmitterParent(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: id, collider ID (nil to detach)
+    // The position and velocity of an attached emitter are relative to the collider
+    let emitter_id = getEmitterId(state, 1)?;
+
+    let parent = if lua_type(state, 2) <= LUA_TNIL {
+        None
+    } else {
+        Some(checkCollider(getColliderId(state, 2)?)?)
+    };
+
+    let emittersPtr = JUICE_SINGLETON.get_emitters();
+    // Block while waiting for access to the mutex
+    let mut emittersLock = emittersPtr.lock().expect("Could not lock emitters (wtf?)");
+    emittersLock.get(emitter_id)?.parent = parent;
+
+    Ok(0)
+}
+
+#[lua_function]
+fn removeEmitter(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: id
+    let emitter_id = getEmitterId(state, 1)?;
+
+    let emittersPtr = JUICE_SINGLETON.get_emitters();
+    // Block while waiting for access to the mutex
+    let mut emittersLock = emittersPtr.lock().expect("Could not lock emitters (wtf?)");
+    emittersLock.remove(emitter_id)?;
+
+    Ok(0)
+}
+
+/// Reads a sink ID from the stack, IDs are handed to Lua as plain numbers
+fn getSinkId(state: LuaState, index: i32) -> Result<Handle, SinkError> {
+    readHandle(state, index).ok_or(SinkError::InvalidId(lua_tonumber(state, index)))
+}
+
+/// Adds a sink, pushing its ID
+fn addSink(state: LuaState, sink: Sink) -> i32 {
+    let sinksPtr = JUICE_SINGLETON.get_sinks();
+    // Block while waiting for access to the mutex
+    let mut sinksLock = sinksPtr.lock().expect("Could not lock sinks (wtf?)");
+    let id = sinksLock.add(sink);
+
+    lua_pushnumber(state, id.to_raw() as f64);
+    1
+}
+
+#[lua_function]
+fn createSink(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: region
+    let region = readRegion(state, 1)?;
+
+    Ok(addSink(state, Sink::new(SinkVolume::Region(region))))
+}
+
+#[lua_function]
+fn createColliderSink(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: collider ID, margin (optional, how far away particles still touch it)
+    let collider_id = checkCollider(getColliderId(state, 1)?)?;
+    let margin = luaL_optnumber(state, 2, DEFAULT_MARGIN as f64) as f32;
+
+    Ok(addSink(
+        state,
+        Sink::new(SinkVolume::Collider {
+            id: collider_id,
+            margin,
+        }),
+    ))
+}
+
+#[lua_function]
+fn getSinkAbsorbed(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: id, reset (optional, starts counting from 0 again)
+    let sink_id = getSinkId(state, 1)?;
+    let reset = lua_toboolean(state, 2) != 0;
+
+    let sinksPtr = JUICE_SINGLETON.get_sinks();
+    // Block while waiting for access to the mutex
+    let mut sinksLock = sinksPtr.lock().expect("Could not lock sinks (wtf?)");
+    let sink = sinksLock.get(sink_id)?;
+
+    lua_pushnumber(state, sink.absorbed as f64);
+    if reset {
+        sink.absorbed = 0;
+    }
+
+    Ok(1)
+}
+
+#[lua_function]
+fn setSinkEnabled(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: id, enabled
+    let sink_id = getSinkId(state, 1)?;
+    let enabled = lua_toboolean(state, 2) != 0;
+
+    let sinksPtr = JUICE_SINGLETON.get_sinks();
+    // Block while waiting for access to the mutex
+    let mut sinksLock = sinksPtr.lock().expect("Could not lock sinks (wtf?)");
+    sinksLock.get(sink_id)?.enabled = enabled;
+
+    Ok(0)
+}
+
+#[lua_function]
+fn removeSink(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: id
+    let sink_id = getSinkId(state, 1)?;
+
+    let sinksPtr = JUICE_SINGLETON.get_sinks();
+    // Block while waiting for access to the mutex
+    let mut sinksLock = sinksPtr.lock().expect("Could not lock sinks (wtf?)");
+    sinksLock.remove(sink_id)?;
+
+    Ok(0)
+}
+
+/// Reads a trigger ID from the stack, IDs are handed to Lua as plain numbers
+fn getTriggerId(state: LuaState, index: i32) -> Result<Handle, TriggerError> {
+    readHandle(state, index).ok_or(TriggerError::InvalidId(lua_tonumber(state, index)))
+}
+
+/// Reads the fill threshold (nil to never fill) at `first`, then the empty threshold (optional, 0 by default)
+/// A trigger has to hold at least one particle to fill, and fewer than that to empty again
+fn readThresholds(state: LuaState, first: i32) -> Result<(Option<u32>, u32), ArgError> {
+    let empty = luaL_optnumber(state, first + 1, 0.0).max(0.0) as u32;
+    if lua_type(state, first) <= LUA_TNIL {
+        return Ok((None, empty));
+    }
+
+    let fill = luaL_checknumber(state, first).max(0.0) as u32;
+    if fill == 0 {
+        return Err(ArgError {
+            index: first,
+            expected: "fill threshold of at least 1",
+        });
+    }
+
+    if empty >= fill {
+        return Err(ArgError {
+            index: first + 1,
+            expected: "empty threshold below the fill threshold",
+        });
+    }
+
+    Ok((Some(fill), empty))
+}
+
+#[lua_function]
+fn createTrigger(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: region, fill threshold (optional), empty threshold (optional, 0 by default)
+    let region = readRegion(state, 1)?;
+    let (fill, empty) = readThresholds(state, 2)?;
+
+    let triggersPtr = JUICE_SINGLETON.get_triggers();
+    // Block while waiting for access to the mutex
+    let mut triggersLock = triggersPtr.lock().expect("Could not lock triggers (wtf?)");
+    let id = triggersLock.add(Trigger::new(region, fill, empty));
+
+    lua_pushnumber(state, id.to_raw() as f64);
+    Ok(1)
+}
+
+#[lua_function]
+fn getTriggerInfo(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: id
+    // Returns the particle count, the mean velocity inside, and whether the trigger is filled, as of the last tick
+    let trigger_id = getTriggerId(state, 1)?;
+
+    let triggersPtr = JUICE_SINGLETON.get_triggers();
+    // Block while waiting for access to the mutex
+    let mut triggersLock = triggersPtr.lock().expect("Could not lock triggers (wtf?)");
+    let trigger = triggersLock.get(trigger_id)?;
+
+    lua_pushnumber(state, trigger.count as f64);
+    pushVector(state, &trigger.meanVelocity);
+    lua_pushboolean(state, trigger.filled as i32);
+
+    Ok(3)
+}
+
+#[lua_function]
+fn setTriggerThresholds(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: id, fill threshold (nil to never fill), empty threshold (optional)
+    let trigger_id = getTriggerId(state, 1)?;
+    let (fill, empty) = readThresholds(state, 2)?;
+
+    let triggersPtr = JUICE_SINGLETON.get_triggers();
+    // Block while waiting for access to the mutex
+    let mut triggersLock = triggersPtr.lock().expect("Could not lock triggers (wtf?)");
+    triggersLock.get(trigger_id)?.setThresholds(fill, empty);
+
+    Ok(0)
+}
+
+#[lua_function]
+fn setTriggerRegion(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: id, region
+    let trigger_id = getTriggerId(state, 1)?;
+    let region = readRegion(state, 2)?;
+
+    let triggersPtr = JUICE_SINGLETON.get_triggers();
+    // Block while waiting for access to the mutex
+    let mut triggersLock = triggersPtr.lock().expect("Could not lock triggers (wtf?)");
+    triggersLock.get(trigger_id)?.region = region;
+
+    Ok(0)
+}
+
+#[lua_function]
+fn setTriggerEnabled(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: id, enabled
+    let trigger_id = getTriggerId(state, 1)?;
+    let enabled = lua_toboolean(state, 2) != 0;
+
+    let triggersPtr = JUICE_SINGLETON.get_triggers();
+    // Block while waiting for access to the mutex
+    let mut triggersLock = triggersPtr.lock().expect("Could not lock triggers (wtf?)");
+    triggersLock.get(trigger_id)?.setEnabled(enabled);
+
+    Ok(0)
+}
+
+#[lua_function]
+fn removeTrigger(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect arguments like this: id
+    let trigger_id = getTriggerId(state, 1)?;
+
+    let triggersPtr = JUICE_SINGLETON.get_triggers();
+    // Block while waiting for access to the mutex
+    let mut triggersLock = triggersPtr.lock().expect("Could not lock triggers (wtf?)");
+    triggersLock.remove(trigger_id)?;
+
+    Ok(0)
+}
+
+#[lua_function]
+fn pollTriggerEvents(state: LuaState) -> Result<i32, std::io::Error> {
+    // Returns every notification since the last poll, oldest first: {id = trigger ID, event = "enter", tick = number}
+    let triggersPtr = JUICE_SINGLETON.get_triggers();
+    // Block while waiting for access to the mutex
+    let mut triggersLock = triggersPtr.lock().expect("Could not lock triggers (wtf?)");
+    let notifications = triggersLock.poll();
+
+    lua_createtable(state, notifications.len() as i32, 0);
+
+    for (i, notification) in notifications.iter().enumerate() {
+        lua_pushinteger(state, i as isize + 1);
+        lua_createtable(state, 0, 3);
+
+        lua_pushnumber(state, notification.trigger.to_raw() as f64);
+        lua_setfield(state, -2, cstr!("id"));
+
+        let event = notification.event.name();
+        lua_pushlstring(state, event.as_ptr() as *const _, event.len());
+        lua_setfield(state, -2, cstr!("event"));
+
+        lua_pushnumber(state, notification.tick as f64);
+        lua_setfield(state, -2, cstr!("tick"));
+
+        lua_settable(state, -3);
+    }
+
+    Ok(1)
+}
+
+#[lua_function]
+fn addParticles(state: LuaState) -> Result<i32, Box<dyn std::error::Error>> {
+    // We expect a table, that.. contains tables
+    // and the table inside of the table is a lua particle struct, simply having 2 members:
+    // pos and vel, both vectors
+
+    // We want a table because if we were to perhaps, make a particle cube or sphere,
+    // we'd have to invoke this function THOUSANDS of times, and we'd have to do it
+    // in a loop, so.. we'll just do it in a table
+
+    // An optional particle group can come after the table, it decides which colliders the particles hit
+    let group = checkGroup(2, luaL_optinteger(state, 2, 0))?;
+    lua_settop(state, 1);
+
+    // Get the particle queue pointer
+    let particlePtr = JUICE_SINGLETON.get_particle_queue();
+    // Block while waiting for access to the mutex
+    let mut particleLock = particlePtr
+        .lock()
+        .expect("Could not lock particle queue (wtf?)");
+    let particleObject = &mut *particleLock;
+
+    let tableLength = lua_objlen(state, -1);
+    let mut particles: Vec<Particle> = Vec::with_capacity(tableLength as usize);
+
+    for i in 0..tableLength {
+        // Lua indices go 1, 2, 3, ...
+        // unlike normal indices, which are 0, 1, 2, ...
+        let real_index = i + 1;
+        lua_pushnumber(state, real_index as f64);
+        lua_gettable(state, -2);
+
+        // The particle structure is at the top of the stack, lets push it and then pop it, for pos and vel
+        lua_getfield(state, -1, cstr!("pos"));
+        let pos = readVector(state, -1).ok_or(EntryError {
+            index: 1,
+            entry: real_index as usize,
+            expected: "particle with a pos Vector",
+        })?;
+        // Pop off the pos
+        lua_pop(state, 1);
+        // Push the vel, particles without one simply start still
+        lua_getfield(state, -1, cstr!("vel"));
+        let vel = readVector(state, -1).unwrap_or(Vec3::new());
+        // Pop off the vel, and while we're at it, additionally pop the particle struct
+        lua_pop(state, 2);
+
+        // Construct our Particle
+        let particle = Particle { pos, vel, group };
+
+        particles.push(particle);
+    }
+
+    for particle in particles {
+        particleObject.add_particle(particle);
+    }
+
+    Ok(0)
+}
+
+#[lua_function]
+fn setParticleGroupChannels(state: LuaState) -> Result<i32, ArgError> {
+    // We expect the arguments like this: particle group, channel mask
+    let group = checkGroup(1, luaL_checkinteger(state, 1))?;
+    let channels = checkChannels(state, 2)?;
+
+    let particlePtr = JUICE_SINGLETON.get_particle_queue();
+    // Block while waiting for access to the mutex
+    let mut particleLock = particlePtr
+        .lock()
+        .expect("Could not lock particle queue (wtf?)");
+    let particleObject = &mut *particleLock;
+    particleObject.setChannels(group, channels);
+
+    Ok(0)
+}
+
 #[lua_function]
 fn clearParticles(_state: LuaState) -> Result<i32, std::io::Error> {
     let particlePtr = JUICE_SINGLETON.get_particle_queue();
@@ -351,12 +2203,67 @@ fn entry(state: LuaState) -> Result<i32, std::io::Error> {
     let juiceLib = reg! [
         "GetParticlePos" => getParticlePositions,
         "CreateCollider" => createCollider,
+        "RegisterMesh" => registerMesh,
+        "CreateMeshInstance" => createMeshInstance,
         "CreatePlayerCollider" => spawnPlayerCollider,
+        "SetCapsuleSize" => setCapsuleSize,
+        "CreateBoxCollider" => createBoxCollider,
+        "CreateCompoundCollider" => createCompoundCollider,
+        "CreateHeightfield" => createHeightfield,
+        "SetHeightfieldHeights" => setHeightfieldHeights,
+        "LoadMap" => loadMap,
+        "LoadMeshCollider" => loadMeshCollider,
         "SetColliderPos" => setColliderPos,
         "SetColliderRot" => setColliderRot,
+        "SetColliderTransforms" => setColliderTransforms,
+        "SetColliderDynamic" => setColliderDynamic,
+        "SetColliderChannels" => setColliderChannels,
         "RemoveCollider" => removeCollider,
+        "SetColliderEnabled" => setColliderEnabled,
+        "SetColliderParent" => setColliderParent,
+        "SetColliderVelocity" => setColliderVelocity,
+        "SetColliderKeyframes" => setColliderKeyframes,
+        "StopColliderMotion" => stopColliderMotion,
+        "GetColliderInfo" => getColliderInfo,
+        "ListColliders" => listColliders,
+        "SetColliderLimit" => setColliderLimit,
+        "ReserveColliders" => reserveColliders,
         "SetParticles" => setParticles,
+        "Explode" => explode,
+        "ApplyImpulse" => applyImpulse,
+        "Vortex" => vortex,
+        "SetRegionVelocity" => setRegionVelocity,
+        "ScaleRegionVelocity" => scaleRegionVelocity,
+        "TeleportRegion" => teleportRegion,
+        "ScheduleEvent" => scheduleEvent,
+        "CancelEvent" => cancelEvent,
+        "GetSimulationTime" => getSimulationTime,
+        "CreateForceField" => createForceField,
+        "SetForceFieldEnabled" => setForceFieldEnabled,
+        "SetForceFieldStrength" => setForceFieldStrength,
+        "SetForceFieldRegion" => setForceFieldRegion,
+        "RemoveForceField" => removeForceField,
+        "CreateEmitter" => createEmitter,
+        "SetEmitterPos" => setEmitterPos,
+        "SetEmitterVelocity" => setEmitterVelocity,
+        "SetEmitterRate" => setEmitterRate,
+        "SetEmitterEnabled" => setEmitterEnabled,
+        "SetEmitterParent" => setEmitterParent,
+        "RemoveEmitter" => removeEmitter,
+        "CreateSink" => createSink,
+        "CreateColliderSink" => createColliderSink,
+        "GetSinkAbsorbed" => getSinkAbsorbed,
+        "SetSinkEnabled" => setSinkEnabled,
+        "RemoveSink" => removeSink,
+        "CreateTrigger" => createTrigger,
+        "GetTriggerInfo" => getTriggerInfo,
+        "SetTriggerThresholds" => setTriggerThresholds,
+        "SetTriggerRegion" => setTriggerRegion,
+        "SetTriggerEnabled" => setTriggerEnabled,
+        "RemoveTrigger" => removeTrigger,
+        "PollTriggerEvents" => pollTriggerEvents,
         "AddParticles" => addParticles,
+        "SetParticleGroupChannels" => setParticleGroupChannels,
         "ClearParticles" => clearParticles
     ];
 
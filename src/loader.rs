@@ -0,0 +1,102 @@
+//! Readers for files on disk which turn into collider geometry
+pub mod bsp;
+pub mod obj;
+pub mod ply;
+pub mod stl;
+
+use crate::geometry::TriangleMesh;
+use std::{
+    fmt,
+    path::{Component, Path, PathBuf},
+};
+
+#[derive(Debug)]
+pub enum MeshLoadError {
+    Io(std::io::Error),
+    /// The file extension isn't one of the mesh formats we can read
+    UnknownFormat(String),
+    /// The file is broken, or uses a part of the format which isn't supported
+    Malformed(&'static str, String),
+}
+
+impl fmt::Display for MeshLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MeshLoadError::Io(error) => write!(f, "Could not read the mesh: {}", error),
+            MeshLoadError::UnknownFormat(extension) => write!(
+                f,
+                "Unknown mesh format \"{}\" (expected obj, stl or ply)",
+                extension
+            ),
+            MeshLoadError::Malformed(format, reason) => {
+                write!(f, "Malformed {} file: {}", format, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MeshLoadError {}
+
+impl From<std::io::Error> for MeshLoadError {
+    fn from(error: std::io::Error) -> Self {
+        MeshLoadError::Io(error)
+    }
+}
+
+/// Reads a mesh from disk, the format is picked from the file extension
+pub fn loadMesh(path: &Path) -> Result<TriangleMesh, MeshLoadError> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+
+    let bytes = std::fs::read(path)?;
+
+    match extension.as_str() {
+        "obj" => obj::parse(&bytes),
+        "stl" => stl::parse(&bytes),
+        "ply" => ply::parse(&bytes),
+        _ => Err(MeshLoadError::UnknownFormat(extension)),
+    }
+}
+
+/// Resolves a path from Lua against the game's root directory, which is the working directory
+///
+/// Only relative paths that stay inside the game's directory are allowed, so Lua can't read any file on the machine
+pub fn gamePath(path: &str) -> Option<PathBuf> {
+    let relative = Path::new(path);
+    let inside = relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if !inside {
+        return None;
+    }
+
+    Some(std::env::current_dir().ok()?.join(relative))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_relative_paths() {
+        let resolved = gamePath("garrysmod/maps/gm_construct.bsp").unwrap();
+        assert_eq!(
+            resolved,
+            std::env::current_dir()
+                .unwrap()
+                .join("garrysmod/maps/gm_construct.bsp")
+        );
+        assert!(gamePath("./garrysmod/maps/gm_flatgrass.bsp").is_some());
+    }
+
+    #[test]
+    fn rejects_escaping_paths() {
+        assert!(gamePath("/etc/passwd").is_none());
+        assert!(gamePath("../secrets.txt").is_none());
+        assert!(gamePath("garrysmod/../../secrets.txt").is_none());
+        assert!(gamePath("garrysmod/maps/..").is_none());
+    }
+}
@@ -0,0 +1,610 @@
+//! A reader for Source engine `.bsp` maps (versions 19 to 21, which covers every GMod map)
+//!
+//! # Brushes
+//! Only the brushes of the world (model 0) are read, brush entities like doors move around and aren't static.
+//! Every brush is already a convex shape bounded by its sides' planes, so it can become a `Convex` collider as is.
+//!
+//! # Displacements
+//! Displacements are triangulated and merged into a single triangle mesh, see `BspGeometry::displacementMesh`
+use crate::vec::{Vec3, Vec4};
+use std::fmt;
+
+pub const CONTENTS_SOLID: i32 = 0x1;
+pub const CONTENTS_WINDOW: i32 = 0x2;
+pub const CONTENTS_GRATE: i32 = 0x8;
+pub const CONTENTS_WATER: i32 = 0x20;
+pub const CONTENTS_PLAYERCLIP: i32 = 0x10000;
+pub const CONTENTS_MONSTERCLIP: i32 = 0x20000;
+
+const HEADER_SIZE: usize = 4 + 4 + 64 * 16 + 4;
+
+const LUMP_PLANES: usize = 1;
+const LUMP_VERTEXES: usize = 3;
+const LUMP_NODES: usize = 5;
+const LUMP_FACES: usize = 7;
+const LUMP_LEAFS: usize = 10;
+const LUMP_EDGES: usize = 12;
+const LUMP_SURFEDGES: usize = 13;
+const LUMP_MODELS: usize = 14;
+const LUMP_LEAFBRUSHES: usize = 17;
+const LUMP_BRUSHES: usize = 18;
+const LUMP_BRUSHSIDES: usize = 19;
+const LUMP_DISPINFO: usize = 26;
+const LUMP_DISP_VERTS: usize = 33;
+
+/// How far outside of a plane a point can be, and still count as inside the brush
+const PLANE_EPSILON: f32 = 0.01;
+
+#[derive(Debug)]
+pub enum BspError {
+    /// The file doesn't start with `VBSP`
+    NotBsp,
+    UnsupportedVersion(i32),
+    /// The lump is LZMA compressed, which only console maps do
+    CompressedLump(usize),
+    /// A lump (or a record inside of one) goes past the end of the file
+    Truncated(&'static str),
+}
+
+impl fmt::Display for BspError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BspError::NotBsp => write!(f, "Not a Source engine BSP file"),
+            BspError::UnsupportedVersion(version) => {
+                write!(
+                    f,
+                    "BSP version {} is not supported (only 19 to 21 are)",
+                    version
+                )
+            }
+            BspError::CompressedLump(lump) => {
+                write!(f, "Lump {} is compressed, which is not supported", lump)
+            }
+            BspError::Truncated(what) => {
+                write!(f, "The BSP is truncated or corrupt (bad {})", what)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BspError {}
+
+/// What to import from the map
+pub struct BspOptions {
+    /// Brushes and displacements are only imported if their contents share a bit with this mask
+    pub contents: i32,
+    pub displacements: bool,
+}
+
+impl Default for BspOptions {
+    fn default() -> Self {
+        Self {
+            contents: CONTENTS_SOLID,
+            displacements: true,
+        }
+    }
+}
+
+pub struct Brush {
+    /// `(normal, -distance)` planes, the normals point out of the brush
+    pub planes: Vec<Vec4>,
+    pub lower: Vec3,
+    pub upper: Vec3,
+    pub contents: i32,
+}
+
+pub struct Displacement {
+    pub vertices: Vec<Vec4>,
+    /// Every 3 indices make up a triangle
+    pub indices: Vec<i32>,
+    pub contents: i32,
+}
+
+pub struct BspGeometry {
+    pub brushes: Vec<Brush>,
+    pub displacements: Vec<Displacement>,
+}
+
+impl BspGeometry {
+    /// Merges every displacement into one indexed triangle mesh, `None` if there are no displacements
+    pub fn displacementMesh(&self) -> Option<(Vec<Vec4>, Vec<i32>)> {
+        if self.displacements.is_empty() {
+            return None;
+        }
+
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+
+        for displacement in self.displacements.iter() {
+            let base = vertices.len() as i32;
+            vertices.extend(displacement.vertices.iter().cloned());
+            indices.extend(displacement.indices.iter().map(|index| index + base));
+        }
+
+        Some((vertices, indices))
+    }
+}
+
+/// A lump's data, along with the version of its layout
+struct Lump<'a> {
+    data: &'a [u8],
+    version: i32,
+}
+
+impl<'a> Lump<'a> {
+    /// Gets the `index`th record of `size` bytes
+    fn record(&self, size: usize, index: usize, what: &'static str) -> Result<&'a [u8], BspError> {
+        self.data
+            .get(index * size..(index + 1) * size)
+            .ok_or(BspError::Truncated(what))
+    }
+
+    fn count(&self, size: usize) -> usize {
+        self.data.len() / size
+    }
+}
+
+fn readI32(data: &[u8], at: usize) -> i32 {
+    i32::from_le_bytes(data[at..at + 4].try_into().unwrap())
+}
+
+fn readU16(data: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes(data[at..at + 2].try_into().unwrap())
+}
+
+fn readI16(data: &[u8], at: usize) -> i16 {
+    i16::from_le_bytes(data[at..at + 2].try_into().unwrap())
+}
+
+fn readF32(data: &[u8], at: usize) -> f32 {
+    f32::from_le_bytes(data[at..at + 4].try_into().unwrap())
+}
+
+fn readVec3(data: &[u8], at: usize) -> Vec3 {
+    Vec3::components(
+        readF32(data, at),
+        readF32(data, at + 4),
+        readF32(data, at + 8),
+    )
+}
+
+fn lump(bytes: &[u8], index: usize) -> Result<Lump<'_>, BspError> {
+    // Each lump header is offset, length, version and the uncompressed size (0 when not compressed)
+    let header = 8 + index * 16;
+    let offset = readI32(bytes, header).max(0) as usize;
+    let length = readI32(bytes, header + 4).max(0) as usize;
+    let version = readI32(bytes, header + 8);
+
+    if readI32(bytes, header + 12) != 0 {
+        return Err(BspError::CompressedLump(index));
+    }
+
+    let data = bytes
+        .get(offset..offset + length)
+        .ok_or(BspError::Truncated("lump"))?;
+
+    Ok(Lump { data, version })
+}
+
+/// Reads the static collision geometry out of a whole `.bsp` file
+pub fn parse(bytes: &[u8], options: &BspOptions) -> Result<BspGeometry, BspError> {
+    if bytes.len() < HEADER_SIZE || &bytes[0..4] != b"VBSP" {
+        return Err(BspError::NotBsp);
+    }
+
+    let version = readI32(bytes, 4);
+    if !(19..=21).contains(&version) {
+        return Err(BspError::UnsupportedVersion(version));
+    }
+
+    let planes = lump(bytes, LUMP_PLANES)?;
+
+    let mut geometry = BspGeometry {
+        brushes: readBrushes(bytes, &planes, options.contents)?,
+        displacements: Vec::new(),
+    };
+
+    if options.displacements {
+        geometry.displacements = readDisplacements(bytes, &planes, options.contents)?;
+    }
+
+    Ok(geometry)
+}
+
+/// Reads a plane as `(normal, distance)`
+fn readPlane(planes: &Lump, index: usize) -> Result<(Vec3, f32), BspError> {
+    let plane = planes.record(20, index, "plane")?;
+    Ok((readVec3(plane, 0), readF32(plane, 12)))
+}
+
+/// Finds every brush reachable from the world model's tree
+fn worldBrushes(bytes: &[u8]) -> Result<Vec<usize>, BspError> {
+    let models = lump(bytes, LUMP_MODELS)?;
+    let nodes = lump(bytes, LUMP_NODES)?;
+    let leafs = lump(bytes, LUMP_LEAFS)?;
+    let leafBrushes = lump(bytes, LUMP_LEAFBRUSHES)?;
+
+    // Old leafs still carry their ambient lighting around
+    let leafSize = if leafs.version == 0 { 56 } else { 32 };
+
+    let headNode = readI32(models.record(48, 0, "model")?, 36);
+
+    let mut brushes = Vec::new();
+    let mut seen = std::collections::HashSet::new();
+    let mut stack = vec![headNode];
+    let mut visitedNodes = 0;
+
+    while let Some(child) = stack.pop() {
+        if child >= 0 {
+            // A tree can't visit more nodes than there are, a corrupt one could loop forever otherwise
+            visitedNodes += 1;
+            if visitedNodes > nodes.count(32) {
+                return Err(BspError::Truncated("node tree"));
+            }
+
+            let node = nodes.record(32, child as usize, "node")?;
+            stack.push(readI32(node, 4));
+            stack.push(readI32(node, 8));
+            continue;
+        }
+
+        // Negative children are leafs
+        let leaf = leafs.record(leafSize, (-1 - child) as usize, "leaf")?;
+        let first = readU16(leaf, 24) as usize;
+        let count = readU16(leaf, 26) as usize;
+
+        for i in first..first + count {
+            let brush = readU16(leafBrushes.record(2, i, "leaf brush")?, 0) as usize;
+            // Brushes span many leafs, only keep the first sighting
+            if seen.insert(brush) {
+                brushes.push(brush);
+            }
+        }
+    }
+
+    brushes.sort_unstable();
+    Ok(brushes)
+}
+
+fn readBrushes(bytes: &[u8], planes: &Lump, mask: i32) -> Result<Vec<Brush>, BspError> {
+    let brushes = lump(bytes, LUMP_BRUSHES)?;
+    let sides = lump(bytes, LUMP_BRUSHSIDES)?;
+
+    let mut result = Vec::new();
+
+    for index in worldBrushes(bytes)? {
+        let brush = brushes.record(12, index, "brush")?;
+        let firstSide = readI32(brush, 0).max(0) as usize;
+        let sideCount = readI32(brush, 4).max(0) as usize;
+        let contents = readI32(brush, 8);
+
+        if contents & mask == 0 {
+            continue;
+        }
+
+        let mut brushPlanes: Vec<(Vec3, f32)> = Vec::with_capacity(sideCount);
+        let mut planeIndices: Vec<u16> = Vec::with_capacity(sideCount);
+
+        for i in firstSide..firstSide + sideCount {
+            let side = sides.record(8, i, "brush side")?;
+
+            let planeIndex = readU16(side, 0);
+            if !planeIndices.contains(&planeIndex) {
+                planeIndices.push(planeIndex);
+                brushPlanes.push(readPlane(planes, planeIndex as usize)?);
+            }
+        }
+
+        if let Some((lower, upper)) = brushBounds(&brushPlanes) {
+            result.push(Brush {
+                planes: brushPlanes
+                    .iter()
+                    .map(|(normal, dist)| Vec4::components(normal.x, normal.y, normal.z, -dist))
+                    .collect(),
+                lower,
+                upper,
+                contents,
+            });
+        }
+    }
+
+    Ok(result)
+}
+
+/// Computes the bounds of a brush from the corners where its planes meet, `None` if the brush has no volume
+fn brushBounds(planes: &[(Vec3, f32)]) -> Option<(Vec3, Vec3)> {
+    let mut bounds: Option<(Vec3, Vec3)> = None;
+
+    for a in 0..planes.len() {
+        for b in a + 1..planes.len() {
+            for c in b + 1..planes.len() {
+                let (n1, d1) = &planes[a];
+                let (n2, d2) = &planes[b];
+                let (n3, d3) = &planes[c];
+
+                let n2xn3 = Vec3::cross(n2, n3);
+                let det = Vec3::dot(n1, &n2xn3);
+                if det.abs() < 1e-6 {
+                    continue;
+                }
+
+                // Where the three planes intersect
+                let point = Vec3::add(
+                    &Vec3::add(&n2xn3.scale(*d1), &Vec3::cross(n3, n1).scale(*d2)),
+                    &Vec3::cross(n1, n2).scale(*d3),
+                )
+                .scale(1.0 / det);
+
+                // Only corners which are inside every other plane are corners of the brush
+                let inside = planes
+                    .iter()
+                    .all(|(normal, dist)| Vec3::dot(normal, &point) - dist <= PLANE_EPSILON);
+                if !inside {
+                    continue;
+                }
+
+                bounds = Some(match bounds {
+                    Some((lower, upper)) => (
+                        Vec3::components(
+                            lower.x.min(point.x),
+                            lower.y.min(point.y),
+                            lower.z.min(point.z),
+                        ),
+                        Vec3::components(
+                            upper.x.max(point.x),
+                            upper.y.max(point.y),
+                            upper.z.max(point.z),
+                        ),
+                    ),
+                    None => (point.clone(), point),
+                });
+            }
+        }
+    }
+
+    bounds
+}
+
+fn readDisplacements(
+    bytes: &[u8],
+    planes: &Lump,
+    mask: i32,
+) -> Result<Vec<Displacement>, BspError> {
+    let dispInfos = lump(bytes, LUMP_DISPINFO)?;
+    let dispVerts = lump(bytes, LUMP_DISP_VERTS)?;
+    let faces = lump(bytes, LUMP_FACES)?;
+    let vertexes = lump(bytes, LUMP_VERTEXES)?;
+    let edges = lump(bytes, LUMP_EDGES)?;
+    let surfEdges = lump(bytes, LUMP_SURFEDGES)?;
+
+    let mut result = Vec::new();
+
+    for index in 0..dispInfos.count(176) {
+        let info = dispInfos.record(176, index, "displacement")?;
+        let startPosition = readVec3(info, 0);
+        let firstVert = readI32(info, 12).max(0) as usize;
+        let power = readI32(info, 20);
+        let contents = readI32(info, 32);
+        let faceIndex = readU16(info, 36) as usize;
+
+        if contents & mask == 0 || !(2..=4).contains(&power) {
+            continue;
+        }
+
+        let face = faces.record(56, faceIndex, "face")?;
+        let (normal, _) = readPlane(planes, readU16(face, 0) as usize)?;
+        let firstEdge = readI32(face, 4).max(0) as usize;
+        if readI16(face, 8) != 4 {
+            // Displacements can only be made out of quads
+            continue;
+        }
+
+        // Walk the face's edges to find its corners
+        let mut corners: Vec<Vec3> = Vec::with_capacity(4);
+        for i in firstEdge..firstEdge + 4 {
+            let surfEdge = readI32(surfEdges.record(4, i, "surface edge")?, 0);
+            let edge = edges.record(4, surfEdge.unsigned_abs() as usize, "edge")?;
+            let vertex = if surfEdge >= 0 {
+                readU16(edge, 0)
+            } else {
+                readU16(edge, 2)
+            };
+
+            corners.push(readVec3(vertexes.record(12, vertex as usize, "vertex")?, 0));
+        }
+
+        // The displacement starts at whichever corner is closest to its start position
+        let start = (0..4)
+            .min_by(|a, b| {
+                let distance = |i: &usize| Vec3::sub(&corners[*i], &startPosition).length();
+                distance(a).total_cmp(&distance(b))
+            })
+            .unwrap();
+        corners.rotate_left(start);
+
+        let side = (1usize << power) + 1;
+        let step = 1.0 / (side - 1) as f32;
+
+        // Rows go from corner 0 to corner 1 (and 3 to 2), columns go across
+        let mut vertices = Vec::with_capacity(side * side);
+        for row in 0..side {
+            let rowStart = Vec3::lerp(&corners[0], &corners[1], row as f32 * step);
+            let rowEnd = Vec3::lerp(&corners[3], &corners[2], row as f32 * step);
+
+            for column in 0..side {
+                let vert =
+                    dispVerts.record(20, firstVert + row * side + column, "displacement vertex")?;
+                let offset = readVec3(vert, 0).scale(readF32(vert, 12));
+                let point = Vec3::add(
+                    &Vec3::lerp(&rowStart, &rowEnd, column as f32 * step),
+                    &offset,
+                );
+
+                vertices.push(Vec4::components(point.x, point.y, point.z, 1.0 / 2.0));
+            }
+        }
+
+        // Keep the triangles facing the same way as the face they were built on
+        let faceNormal = Vec3::cross(
+            &Vec3::sub(&corners[1], &corners[0]),
+            &Vec3::sub(&corners[3], &corners[0]),
+        );
+        let flip = Vec3::dot(&faceNormal, &normal) < 0.0;
+
+        let mut indices = Vec::with_capacity((side - 1) * (side - 1) * 6);
+        for row in 0..side - 1 {
+            for column in 0..side - 1 {
+                let corner = (row * side + column) as i32;
+                let next = corner + side as i32;
+
+                for triangle in [[corner, next, next + 1], [corner, next + 1, corner + 1]] {
+                    if flip {
+                        indices.extend_from_slice(&[triangle[0], triangle[2], triangle[1]]);
+                    } else {
+                        indices.extend_from_slice(&triangle);
+                    }
+                }
+            }
+        }
+
+        result.push(Displacement {
+            vertices,
+            indices,
+            contents,
+        });
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A tiny hand-built map:
+    /// - brushes 0 and 2 are solid cubes, 1 is water, 3 is solid but only belongs to a brush entity
+    /// - brush 1 sits in both leafs of the world, so it must only be read once
+    /// - a 256x256 power 2 displacement on the floor, with its middle vertex raised by 8 units
+    /// - a second copy of that displacement, marked as water
+    const SMALL_MAP: &[u8] = include_bytes!("fixtures/small.bsp");
+
+    #[test]
+    fn solid_world_brushes() {
+        let geometry = parse(SMALL_MAP, &BspOptions::default()).unwrap();
+
+        assert_eq!(geometry.brushes.len(), 2);
+        for brush in geometry.brushes.iter() {
+            assert_eq!(brush.contents, CONTENTS_SOLID);
+            assert_eq!(brush.planes.len(), 6);
+        }
+
+        let cube = &geometry.brushes[0];
+        assert_eq!((cube.lower.x, cube.lower.y, cube.lower.z), (0.0, 0.0, 0.0));
+        assert_eq!(
+            (cube.upper.x, cube.upper.y, cube.upper.z),
+            (64.0, 64.0, 64.0)
+        );
+
+        let raised = &geometry.brushes[1];
+        assert_eq!((raised.lower.z, raised.upper.z), (200.0, 232.0));
+    }
+
+    #[test]
+    fn content_filtering() {
+        let options = BspOptions {
+            contents: CONTENTS_SOLID | CONTENTS_WATER,
+            displacements: true,
+        };
+        let geometry = parse(SMALL_MAP, &options).unwrap();
+        assert_eq!(geometry.brushes.len(), 3);
+        assert_eq!(geometry.displacements.len(), 2);
+
+        let options = BspOptions {
+            contents: CONTENTS_WATER,
+            displacements: true,
+        };
+        let geometry = parse(SMALL_MAP, &options).unwrap();
+        assert_eq!(geometry.brushes.len(), 1);
+        assert_eq!(geometry.brushes[0].contents, CONTENTS_WATER);
+        assert_eq!(geometry.displacements.len(), 1);
+        assert_eq!(geometry.displacements[0].contents, CONTENTS_WATER);
+
+        let options = BspOptions {
+            contents: CONTENTS_PLAYERCLIP,
+            displacements: true,
+        };
+        let geometry = parse(SMALL_MAP, &options).unwrap();
+        assert!(geometry.brushes.is_empty());
+        assert!(geometry.displacementMesh().is_none());
+    }
+
+    #[test]
+    fn displacements() {
+        let geometry = parse(SMALL_MAP, &BspOptions::default()).unwrap();
+        assert_eq!(geometry.displacements.len(), 1);
+
+        // Power 2 is a 5x5 grid of vertices, so 4x4 quads of 2 triangles each
+        let displacement = &geometry.displacements[0];
+        assert_eq!(displacement.vertices.len(), 25);
+        assert_eq!(displacement.indices.len(), 4 * 4 * 2 * 3);
+
+        let middle = &displacement.vertices[12];
+        assert_eq!((middle.x, middle.y, middle.z), (128.0, 128.0, 8.0));
+
+        // The floor faces up, and so should every triangle
+        for triangle in displacement.indices.chunks_exact(3) {
+            let corner = |i: i32| {
+                let vertex = &displacement.vertices[i as usize];
+                Vec3::components(vertex.x, vertex.y, vertex.z)
+            };
+            let normal = Vec3::cross(
+                &Vec3::sub(&corner(triangle[1]), &corner(triangle[0])),
+                &Vec3::sub(&corner(triangle[2]), &corner(triangle[0])),
+            );
+            assert!(normal.z > 0.0);
+        }
+
+        let options = BspOptions {
+            contents: CONTENTS_SOLID,
+            displacements: false,
+        };
+        assert!(parse(SMALL_MAP, &options).unwrap().displacements.is_empty());
+    }
+
+    #[test]
+    fn merged_displacement_mesh() {
+        let options = BspOptions {
+            contents: CONTENTS_SOLID | CONTENTS_WATER,
+            displacements: true,
+        };
+        let (vertices, indices) = parse(SMALL_MAP, &options)
+            .unwrap()
+            .displacementMesh()
+            .unwrap();
+
+        assert_eq!(vertices.len(), 50);
+        assert_eq!(indices.len(), 2 * 96);
+        assert!(indices[96..].iter().all(|index| (25..50).contains(index)));
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(matches!(
+            parse(b"not a map", &BspOptions::default()),
+            Err(BspError::NotBsp)
+        ));
+
+        let mut future = SMALL_MAP.to_vec();
+        future[4..8].copy_from_slice(&22i32.to_le_bytes());
+        assert!(matches!(
+            parse(&future, &BspOptions::default()),
+            Err(BspError::UnsupportedVersion(22))
+        ));
+
+        let truncated = &SMALL_MAP[..SMALL_MAP.len() - 100];
+        assert!(matches!(
+            parse(truncated, &BspOptions::default()),
+            Err(BspError::Truncated(_))
+        ));
+    }
+}
@@ -0,0 +1,102 @@
+//! A reader for Wavefront `.obj` meshes
+//!
+//! Only vertex positions (`v`) and faces (`f`) are read, everything else (normals, UVs, materials, groups) is skipped.
+//! Faces with more than 3 vertices are split into a triangle fan.
+use super::MeshLoadError;
+use crate::{geometry::TriangleMesh, vec::Vec3};
+
+fn malformed(line: usize, reason: &str) -> MeshLoadError {
+    MeshLoadError::Malformed("OBJ", format!("line {}: {}", line, reason))
+}
+
+pub fn parse(bytes: &[u8]) -> Result<TriangleMesh, MeshLoadError> {
+    let text = String::from_utf8_lossy(bytes);
+    let mut mesh = TriangleMesh::new();
+
+    for (number, line) in text.lines().enumerate() {
+        let number = number + 1;
+        let mut words = line.split_whitespace();
+
+        match words.next() {
+            Some("v") => {
+                let mut coordinate = || -> Result<f32, MeshLoadError> {
+                    words
+                        .next()
+                        .and_then(|word| word.parse().ok())
+                        .ok_or_else(|| malformed(number, "vertex needs 3 coordinates"))
+                };
+
+                mesh.vertices.push(Vec3::components(
+                    coordinate()?,
+                    coordinate()?,
+                    coordinate()?,
+                ));
+            }
+            Some("f") => {
+                let mut corners: Vec<u32> = Vec::new();
+
+                for word in words {
+                    // Corners look like `v`, `v/vt`, `v//vn` or `v/vt/vn`, only the position matters
+                    let index: i64 = word
+                        .split('/')
+                        .next()
+                        .and_then(|index| index.parse().ok())
+                        .ok_or_else(|| malformed(number, "bad face index"))?;
+
+                    // Indices start at 1, negative ones count back from the latest vertex
+                    let count = mesh.vertices.len() as i64;
+                    let resolved = if index < 0 { count + index } else { index - 1 };
+                    if resolved < 0 || resolved >= count {
+                        return Err(malformed(number, "face index out of range"));
+                    }
+
+                    corners.push(resolved as u32);
+                }
+
+                if corners.len() < 3 {
+                    return Err(malformed(number, "face needs at least 3 vertices"));
+                }
+
+                for i in 1..corners.len() - 1 {
+                    mesh.indices
+                        .extend_from_slice(&[corners[0], corners[i], corners[i + 1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(mesh)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negative_and_relative_indices() {
+        let text = b"v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nv 0 0 1\nf -4 -3 -1\n";
+        let mesh = parse(text).unwrap();
+
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.indices, vec![0, 1, 2, 0, 1, 3]);
+    }
+
+    #[test]
+    fn faces_with_uvs_and_normals() {
+        let text = b"v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvt 0 0\nvn 0 0 1\n\
+            f 1/1/1 2/1/1 3/1/1\nf 1//1 3//1 4//1\nf 1/1 2/1 3/1 4/1\n";
+        let mesh = parse(text).unwrap();
+
+        // The quad at the end is split into a fan of 2 triangles
+        assert_eq!(mesh.triangleCount(), 4);
+        assert_eq!(&mesh.indices[6..], &[0, 1, 2, 0, 2, 3]);
+    }
+
+    #[test]
+    fn out_of_range_indices() {
+        assert!(parse(b"v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4\n").is_err());
+        assert!(parse(b"v 0 0 0\nv 1 0 0\nv 0 1 0\nf -4 1 2\n").is_err());
+        assert!(parse(b"v 0 0 0\nv 1 0 0\nv 0 1 0\nf 0 1 2\n").is_err());
+    }
+}
@@ -0,0 +1,335 @@
+//! A reader for `.ply` meshes, in ASCII or binary (either byte order)
+//!
+//! Only the `x`, `y` and `z` properties of `vertex` and the `vertex_indices` (or `vertex_index`) list of `face`
+//! are used, every other element and property is read past. Faces with more than 3 vertices are split into a triangle fan.
+use super::MeshLoadError;
+use crate::{geometry::TriangleMesh, vec::Vec3};
+
+fn malformed(reason: &str) -> MeshLoadError {
+    MeshLoadError::Malformed("PLY", reason.to_string())
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Ascii,
+    LittleEndian,
+    BigEndian,
+}
+
+#[derive(Clone, Copy)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Result<Self, MeshLoadError> {
+        Ok(match name {
+            "char" | "int8" => Scalar::I8,
+            "uchar" | "uint8" => Scalar::U8,
+            "short" | "int16" => Scalar::I16,
+            "ushort" | "uint16" => Scalar::U16,
+            "int" | "int32" => Scalar::I32,
+            "uint" | "uint32" => Scalar::U32,
+            "float" | "float32" => Scalar::F32,
+            "double" | "float64" => Scalar::F64,
+            _ => return Err(malformed("unknown property type")),
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+}
+
+enum Property {
+    Scalar(String, Scalar),
+    /// A count, then that many values
+    List(String, Scalar, Scalar),
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// Reads values one after another out of the body of the file
+struct Body<'a> {
+    format: Format,
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Body<'a> {
+    fn read(&mut self, scalar: Scalar) -> Result<f64, MeshLoadError> {
+        if self.format == Format::Ascii {
+            return self.readWord();
+        }
+
+        let size = scalar.size();
+        let data = self
+            .bytes
+            .get(self.position..self.position + size)
+            .ok_or_else(|| malformed("file ends early"))?;
+        self.position += size;
+
+        let mut raw = [0u8; 8];
+        raw[..size].copy_from_slice(data);
+        if self.format == Format::BigEndian {
+            raw[..size].reverse();
+        }
+
+        Ok(match scalar {
+            Scalar::I8 => raw[0] as i8 as f64,
+            Scalar::U8 => raw[0] as f64,
+            Scalar::I16 => i16::from_le_bytes([raw[0], raw[1]]) as f64,
+            Scalar::U16 => u16::from_le_bytes([raw[0], raw[1]]) as f64,
+            Scalar::I32 => i32::from_le_bytes(raw[..4].try_into().unwrap()) as f64,
+            Scalar::U32 => u32::from_le_bytes(raw[..4].try_into().unwrap()) as f64,
+            Scalar::F32 => f32::from_le_bytes(raw[..4].try_into().unwrap()) as f64,
+            Scalar::F64 => f64::from_le_bytes(raw),
+        })
+    }
+
+    fn readWord(&mut self) -> Result<f64, MeshLoadError> {
+        while self
+            .bytes
+            .get(self.position)
+            .is_some_and(u8::is_ascii_whitespace)
+        {
+            self.position += 1;
+        }
+
+        let start = self.position;
+        while self
+            .bytes
+            .get(self.position)
+            .is_some_and(|byte| !byte.is_ascii_whitespace())
+        {
+            self.position += 1;
+        }
+
+        std::str::from_utf8(&self.bytes[start..self.position])
+            .ok()
+            .and_then(|word| word.parse().ok())
+            .ok_or_else(|| malformed("bad number"))
+    }
+}
+
+pub fn parse(bytes: &[u8]) -> Result<TriangleMesh, MeshLoadError> {
+    let (format, elements, bodyStart) = parseHeader(bytes)?;
+    let mut body = Body {
+        format,
+        bytes,
+        position: bodyStart,
+    };
+
+    let mut mesh = TriangleMesh::new();
+
+    for element in elements.iter() {
+        for _ in 0..element.count {
+            let mut position = [0.0f32; 3];
+            let mut corners: Vec<u32> = Vec::new();
+
+            for property in element.properties.iter() {
+                match property {
+                    Property::Scalar(name, scalar) => {
+                        let value = body.read(*scalar)?;
+                        match name.as_str() {
+                            "x" => position[0] = value as f32,
+                            "y" => position[1] = value as f32,
+                            "z" => position[2] = value as f32,
+                            _ => {}
+                        }
+                    }
+                    Property::List(name, countType, valueType) => {
+                        let count = body.read(*countType)? as usize;
+                        let isIndices = name == "vertex_indices" || name == "vertex_index";
+
+                        for _ in 0..count {
+                            let value = body.read(*valueType)?;
+                            if isIndices {
+                                corners.push(value as u32);
+                            }
+                        }
+                    }
+                }
+            }
+
+            match element.name.as_str() {
+                "vertex" => {
+                    mesh.vertices
+                        .push(Vec3::components(position[0], position[1], position[2]))
+                }
+                "face" if corners.len() >= 3 => {
+                    for i in 1..corners.len() - 1 {
+                        mesh.indices
+                            .extend_from_slice(&[corners[0], corners[i], corners[i + 1]]);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let vertexCount = mesh.vertices.len() as u32;
+    if mesh.indices.iter().any(|index| *index >= vertexCount) {
+        return Err(malformed("face index out of range"));
+    }
+
+    Ok(mesh)
+}
+
+/// Reads the header, returning the format, the elements and where the body starts
+fn parseHeader(bytes: &[u8]) -> Result<(Format, Vec<Element>, usize), MeshLoadError> {
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut position = 0;
+    let mut first = true;
+
+    loop {
+        let end = bytes[position..]
+            .iter()
+            .position(|byte| *byte == b'\n')
+            .ok_or_else(|| malformed("header never ends"))?;
+        let line = String::from_utf8_lossy(&bytes[position..position + end]).to_string();
+        position += end + 1;
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        if first {
+            if words.first() != Some(&"ply") {
+                return Err(malformed("missing the ply magic"));
+            }
+
+            first = false;
+            continue;
+        }
+
+        match words.as_slice() {
+            ["format", kind, ..] => {
+                format = Some(match *kind {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::LittleEndian,
+                    "binary_big_endian" => Format::BigEndian,
+                    _ => return Err(malformed("unknown format")),
+                });
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count.parse().map_err(|_| malformed("bad element count"))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", countType, valueType, name] => elements
+                .last_mut()
+                .ok_or_else(|| malformed("property outside of an element"))?
+                .properties
+                .push(Property::List(
+                    name.to_string(),
+                    Scalar::parse(countType)?,
+                    Scalar::parse(valueType)?,
+                )),
+            ["property", scalar, name] => elements
+                .last_mut()
+                .ok_or_else(|| malformed("property outside of an element"))?
+                .properties
+                .push(Property::Scalar(name.to_string(), Scalar::parse(scalar)?)),
+            ["end_header"] => break,
+            _ => {}
+        }
+    }
+
+    let format = format.ok_or_else(|| malformed("missing the format"))?;
+    Ok((format, elements, position))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "element vertex 4\n\
+        property float x\nproperty float y\nproperty float z\nproperty uchar red\n\
+        element face 1\nproperty list uchar int vertex_indices\nend_header\n";
+
+    fn binary(format: &str, bigEndian: bool) -> Vec<u8> {
+        let mut bytes = format!("ply\nformat {} 1.0\n{}", format, HEADER).into_bytes();
+
+        let corners = [
+            [0.0f32, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [0.0, 1.0, 0.0],
+        ];
+        for corner in corners {
+            for value in corner {
+                let raw = if bigEndian {
+                    value.to_be_bytes()
+                } else {
+                    value.to_le_bytes()
+                };
+                bytes.extend_from_slice(&raw);
+            }
+            // The colour, which is read past
+            bytes.push(255);
+        }
+
+        bytes.push(4);
+        for index in 0..4i32 {
+            let raw = if bigEndian {
+                index.to_be_bytes()
+            } else {
+                index.to_le_bytes()
+            };
+            bytes.extend_from_slice(&raw);
+        }
+
+        bytes
+    }
+
+    fn assertQuad(mesh: &TriangleMesh) {
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.vertices[2].x, 1.0);
+        assert_eq!(mesh.vertices[2].y, 1.0);
+        assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3]);
+    }
+
+    #[test]
+    fn ascii() {
+        let text = format!(
+            "ply\nformat ascii 1.0\ncomment made by hand\n{}\
+            0 0 0 255\n1 0 0 255\n1 1 0 255\n0 1 0 255\n4 0 1 2 3\n",
+            HEADER
+        );
+        assertQuad(&parse(text.as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn binary_either_byte_order() {
+        assertQuad(&parse(&binary("binary_little_endian", false)).unwrap());
+        assertQuad(&parse(&binary("binary_big_endian", true)).unwrap());
+    }
+
+    #[test]
+    fn truncated() {
+        let bytes = binary("binary_little_endian", false);
+        assert!(parse(&bytes[..bytes.len() - 2]).is_err());
+
+        let text = format!("ply\nformat ascii 1.0\n{}0 0 0 255\n1 0 0", HEADER);
+        assert!(parse(text.as_bytes()).is_err());
+
+        // The header itself is cut off
+        assert!(parse(b"ply\nformat ascii 1.0\nelement vertex 4").is_err());
+    }
+}
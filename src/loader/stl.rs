@@ -0,0 +1,148 @@
+//! A reader for binary and ASCII `.stl` meshes
+//!
+//! STL has no shared vertices, every triangle brings its own 3 corners. Weld the mesh afterwards to join them up.
+use super::MeshLoadError;
+use crate::{geometry::TriangleMesh, vec::Vec3};
+
+/// The header, then the triangle count
+const BINARY_HEADER_SIZE: usize = 80 + 4;
+/// A normal, 3 corners and a 2 byte attribute
+const BINARY_TRIANGLE_SIZE: usize = 12 + 3 * 12 + 2;
+
+pub fn parse(bytes: &[u8]) -> Result<TriangleMesh, MeshLoadError> {
+    // ASCII files start with "solid", but so do some binary ones, so the size is the better tell
+    if bytes.len() >= BINARY_HEADER_SIZE {
+        let count = u32::from_le_bytes(bytes[80..84].try_into().unwrap()) as usize;
+        if bytes.len() == BINARY_HEADER_SIZE + count * BINARY_TRIANGLE_SIZE {
+            return Ok(parseBinary(bytes, count));
+        }
+    }
+
+    parseAscii(bytes)
+}
+
+fn parseBinary(bytes: &[u8], count: usize) -> TriangleMesh {
+    let float = |at: usize| f32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+    let mut mesh = TriangleMesh::new();
+
+    for triangle in 0..count {
+        // Skip the normal, FleX works the facing out from the winding
+        let start = BINARY_HEADER_SIZE + triangle * BINARY_TRIANGLE_SIZE + 12;
+
+        for corner in 0..3 {
+            let at = start + corner * 12;
+            mesh.indices.push(mesh.vertices.len() as u32);
+            mesh.vertices
+                .push(Vec3::components(float(at), float(at + 4), float(at + 8)));
+        }
+    }
+
+    mesh
+}
+
+fn parseAscii(bytes: &[u8]) -> Result<TriangleMesh, MeshLoadError> {
+    let text = String::from_utf8_lossy(bytes);
+    if !text.trim_start().starts_with("solid") {
+        return Err(MeshLoadError::Malformed(
+            "STL",
+            "neither a binary nor an ASCII STL".to_string(),
+        ));
+    }
+
+    let mut mesh = TriangleMesh::new();
+    let mut words = text.split_whitespace();
+
+    while let Some(word) = words.next() {
+        if word != "vertex" {
+            continue;
+        }
+
+        let mut coordinate = || -> Result<f32, MeshLoadError> {
+            words
+                .next()
+                .and_then(|word| word.parse().ok())
+                .ok_or_else(|| {
+                    MeshLoadError::Malformed("STL", "vertex needs 3 coordinates".to_string())
+                })
+        };
+
+        mesh.vertices.push(Vec3::components(
+            coordinate()?,
+            coordinate()?,
+            coordinate()?,
+        ));
+    }
+
+    if mesh.vertices.len() % 3 != 0 {
+        return Err(MeshLoadError::Malformed(
+            "STL",
+            "facets need exactly 3 vertices".to_string(),
+        ));
+    }
+
+    mesh.indices = (0..mesh.vertices.len() as u32).collect();
+    Ok(mesh)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binary(header: &[u8], triangles: &[[f32; 9]]) -> Vec<u8> {
+        let mut bytes = vec![0u8; 80];
+        bytes[..header.len()].copy_from_slice(header);
+        bytes.extend_from_slice(&(triangles.len() as u32).to_le_bytes());
+
+        for triangle in triangles {
+            // The normal, which is never read
+            bytes.extend_from_slice(&[0u8; 12]);
+            for value in triangle {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            bytes.extend_from_slice(&[0u8; 2]);
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn binary_with_solid_header() {
+        let bytes = binary(
+            b"solid exported by a tool which should know better",
+            &[
+                [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 0.0, 1.0, 1.0],
+            ],
+        );
+        let mesh = parse(&bytes).unwrap();
+
+        assert_eq!(mesh.triangleCount(), 2);
+        assert_eq!(mesh.indices, vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(mesh.vertices[4].x, 1.0);
+        assert_eq!(mesh.vertices[5].z, 1.0);
+    }
+
+    #[test]
+    fn ascii() {
+        let text = b"solid triangle\n\
+            facet normal 0 0 1\n outer loop\n\
+            vertex 0 0 0\n vertex 1 0 0\n vertex 0 1 0\n\
+            endloop\nendfacet\nendsolid triangle\n";
+        let mesh = parse(text).unwrap();
+
+        assert_eq!(mesh.triangleCount(), 1);
+        assert_eq!(mesh.vertices[2].y, 1.0);
+    }
+
+    #[test]
+    fn truncated() {
+        let mut bytes = binary(b"", &[[0.0; 9]]);
+        bytes.truncate(bytes.len() - 1);
+        assert!(parse(&bytes).is_err());
+
+        assert!(
+            parse(b"solid broken\nfacet normal 0 0 1\nouter loop\nvertex 0 0 0\nvertex 1 0")
+                .is_err()
+        );
+    }
+}
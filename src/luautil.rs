@@ -0,0 +1,207 @@
+//! Helpers for reading arguments from Lua
+//!
+//! # Vectors and Angles
+//! Anything taking a position, velocity, bound or rotation accepts the real GMod `Vector` and `Angle` userdata,
+//! along with plain tables (`{x, y, z}`, `{x, y, z, w}` for quaternions, `{p, y, r}` for angles)
+use crate::vec::{Quat, Vec3};
+use rglua::{
+    prelude::*,
+    userdata::{Angle, Vector},
+};
+use std::{fmt, os::raw::c_char};
+
+/// The layout of every GMod userdata, the actual object lives behind `data`
+#[repr(C)]
+#[allow(dead_code)]
+struct GmodUserData {
+    data: *mut std::ffi::c_void,
+    typ: u8,
+}
+
+/// An argument from Lua wasn't what the function expected
+#[derive(Debug)]
+pub struct ArgError {
+    pub index: i32,
+    pub expected: &'static str,
+}
+
+impl fmt::Display for ArgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "bad argument #{} ({} expected)",
+            self.index, self.expected
+        )
+    }
+}
+
+impl std::error::Error for ArgError {}
+
+/// An entry of a table argument from Lua wasn't what the function expected
+#[derive(Debug)]
+pub struct EntryError {
+    pub index: i32,
+    /// The key of the bad entry, starting at 1 like Lua
+    pub entry: usize,
+    pub expected: &'static str,
+}
+
+impl fmt::Display for EntryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "bad argument #{} ({} expected at entry {})",
+            self.index, self.expected, self.entry
+        )
+    }
+}
+
+impl std::error::Error for EntryError {}
+
+/// Turns a relative stack index into an absolute one, so pushing values doesn't invalidate it
+pub fn absIndex(state: LuaState, index: i32) -> i32 {
+    if index < 0 && index > LUA_REGISTRYINDEX {
+        lua_gettop(state) + index + 1
+    } else {
+        index
+    }
+}
+
+/// Checks if the value at the index is a userdata with the GMod metatable `name`
+fn isGmodType(state: LuaState, index: i32, name: *const c_char) -> bool {
+    if lua_type(state, index) != LUA_TUSERDATA || lua_getmetatable(state, index) == 0 {
+        return false;
+    }
+
+    lua_getfield(state, LUA_REGISTRYINDEX, name);
+    let matches = lua_rawequal(state, -1, -2) != 0;
+    lua_pop(state, 2);
+
+    matches
+}
+
+/// Gets the object behind a GMod userdata, the type must already be checked with `isGmodType`
+unsafe fn gmodData<T: Clone>(state: LuaState, index: i32) -> T {
+    let userdata = lua_touserdata(state, index) as *mut GmodUserData;
+    (*((*userdata).data as *mut T)).clone()
+}
+
+/// Reads a number from a field of the table at the index, `None` if the field isn't a number
+pub fn tableNumber(state: LuaState, index: i32, key: *const c_char) -> Option<f32> {
+    lua_getfield(state, index, key);
+    let number = if lua_type(state, -1) == LUA_TNUMBER {
+        Some(lua_tonumber(state, -1) as f32)
+    } else {
+        None
+    };
+    lua_pop(state, 1);
+
+    number
+}
+
+/// Reads a string from a field of the table at the index, `None` if the field isn't a string
+pub fn tableString(state: LuaState, index: i32, key: *const c_char) -> Option<String> {
+    lua_getfield(state, index, key);
+    let string = if lua_type(state, -1) == LUA_TSTRING {
+        Some(rstr!(lua_tolstring(state, -1, std::ptr::null_mut())).to_string())
+    } else {
+        None
+    };
+    lua_pop(state, 1);
+
+    string
+}
+
+/// Reads a `Vector` or a `{x, y, z}` table
+pub fn readVector(state: LuaState, index: i32) -> Option<Vec3> {
+    let index = absIndex(state, index);
+
+    if isGmodType(state, index, cstr!("Vector")) {
+        let vector: Vector = unsafe { gmodData(state, index) };
+        return Some(Vec3::components(vector.x, vector.y, vector.z));
+    }
+
+    if lua_type(state, index) != LUA_TTABLE {
+        return None;
+    }
+
+    Some(Vec3::components(
+        tableNumber(state, index, cstr!("x"))?,
+        tableNumber(state, index, cstr!("y"))?,
+        tableNumber(state, index, cstr!("z"))?,
+    ))
+}
+
+/// Reads an `Angle`, a `{p, y, r}` table or a `{x, y, z, w}` quaternion table, always returning a quaternion
+pub fn readRotation(state: LuaState, index: i32) -> Option<Quat> {
+    let index = absIndex(state, index);
+
+    if isGmodType(state, index, cstr!("Angle")) {
+        let angle: Angle = unsafe { gmodData(state, index) };
+        return Some(Quat::from_angles(angle.p, angle.y, angle.r));
+    }
+
+    if lua_type(state, index) != LUA_TTABLE {
+        return None;
+    }
+
+    if let Some(w) = tableNumber(state, index, cstr!("w")) {
+        return Some(Quat::components(
+            tableNumber(state, index, cstr!("x"))?,
+            tableNumber(state, index, cstr!("y"))?,
+            tableNumber(state, index, cstr!("z"))?,
+            w,
+        ));
+    }
+
+    Some(Quat::from_angles(
+        tableNumber(state, index, cstr!("p"))?,
+        tableNumber(state, index, cstr!("y"))?,
+        tableNumber(state, index, cstr!("r"))?,
+    ))
+}
+
+/// Like `readVector`, but raises an `ArgError` when the argument isn't a vector
+pub fn checkVector(state: LuaState, index: i32) -> Result<Vec3, ArgError> {
+    readVector(state, index).ok_or(ArgError {
+        index,
+        expected: "Vector",
+    })
+}
+
+/// Like `readRotation`, but raises an `ArgError` when the argument isn't a rotation
+pub fn checkRotation(state: LuaState, index: i32) -> Result<Quat, ArgError> {
+    readRotation(state, index).ok_or(ArgError {
+        index,
+        expected: "Angle or quaternion",
+    })
+}
+
+/// Pushes a `{x, y, z}` table, which every function taking a vector accepts back
+pub fn pushVector(state: LuaState, vector: &Vec3) {
+    lua_createtable(state, 0, 3);
+    lua_pushnumber(state, vector.x.into());
+    lua_setfield(state, -2, cstr!("x"));
+
+    lua_pushnumber(state, vector.y.into());
+    lua_setfield(state, -2, cstr!("y"));
+
+    lua_pushnumber(state, vector.z.into());
+    lua_setfield(state, -2, cstr!("z"));
+}
+
+/// Pushes a `{x, y, z, w}` quaternion table, which every function taking a rotation accepts back
+pub fn pushQuat(state: LuaState, quat: &Quat) {
+    lua_createtable(state, 0, 4);
+    lua_pushnumber(state, quat.x.into());
+    lua_setfield(state, -2, cstr!("x"));
+
+    lua_pushnumber(state, quat.y.into());
+    lua_setfield(state, -2, cstr!("y"));
+
+    lua_pushnumber(state, quat.z.into());
+    lua_setfield(state, -2, cstr!("z"));
+
+    lua_pushnumber(state, quat.w.into());
+    lua_setfield(state, -2, cstr!("w"));
+}
@@ -0,0 +1,109 @@
+//! Motion that colliders follow on their own, integrated by the solver thread every tick
+//!
+//! # Velocity
+//! A linear velocity (units per second) and an angular velocity (an axis scaled by degrees per second, in world space).
+//!
+//! # Keyframes
+//! A track of positions and rotations at points in time (seconds), played back once or looped.
+//! The collider is interpolated between keyframes, so the solver sees smooth motion even between Lua frames.
+use crate::vec::{Quat, Vec3};
+
+pub struct Keyframe {
+    /// Seconds since the start of the track
+    pub time: f32,
+    pub position: Vec3,
+    pub rotation: Quat,
+}
+
+pub struct KeyframeTrack {
+    /// Sorted by time, never empty
+    keys: Vec<Keyframe>,
+    /// How far into the track the playback is
+    time: f32,
+    pub looping: bool,
+}
+
+impl KeyframeTrack {
+    /// Creates a track from keyframes in any order, `None` if there aren't any
+    pub fn new(mut keys: Vec<Keyframe>, looping: bool) -> Option<Self> {
+        if keys.is_empty() {
+            return None;
+        }
+
+        keys.sort_by(|a, b| a.time.total_cmp(&b.time));
+        for key in keys.iter_mut() {
+            key.rotation = key.rotation.normalized();
+        }
+
+        Some(Self {
+            time: keys[0].time,
+            keys,
+            looping,
+        })
+    }
+
+    /// The time of the first and last keyframe
+    fn span(&self) -> (f32, f32) {
+        (self.keys[0].time, self.keys[self.keys.len() - 1].time)
+    }
+
+    /// Moves the playback forward
+    pub fn advance(&mut self, dt: f32) {
+        let (start, end) = self.span();
+        self.time += dt;
+
+        if self.looping && end > start && self.time > end {
+            self.time = start + (self.time - start) % (end - start);
+        }
+    }
+
+    /// The transform at the current point of the playback
+    pub fn sample(&self) -> (Vec3, Quat) {
+        // Find the first keyframe after the current time, we're somewhere between it and the one before it
+        let next = self.keys.partition_point(|key| key.time <= self.time);
+
+        if next == 0 {
+            let first = &self.keys[0];
+            return (first.position.clone(), first.rotation.clone());
+        }
+
+        if next == self.keys.len() {
+            let last = &self.keys[next - 1];
+            return (last.position.clone(), last.rotation.clone());
+        }
+
+        let from = &self.keys[next - 1];
+        let to = &self.keys[next];
+        let fraction = (self.time - from.time) / (to.time - from.time);
+
+        (
+            Vec3::lerp(&from.position, &to.position, fraction),
+            Quat::slerp(&from.rotation, &to.rotation, fraction),
+        )
+    }
+}
+
+pub enum Motion {
+    Velocity { linear: Vec3, angular: Vec3 },
+    Keyframes(KeyframeTrack),
+}
+
+impl Motion {
+    /// Advances the motion by `dt` seconds, returning the new transform of a collider at `position` and `rotation`
+    pub fn step(&mut self, dt: f32, position: &Vec3, rotation: &Quat) -> (Vec3, Quat) {
+        match self {
+            Motion::Velocity { linear, angular } => {
+                let spin = Quat::from_axis_angle(angular, (angular.length() * dt).to_radians());
+
+                (
+                    Vec3::add(position, &linear.scale(dt)),
+                    Quat::quat_mul(&spin, &rotation.normalized()),
+                )
+            }
+            Motion::Keyframes(track) => {
+                track.advance(dt);
+                track.sample()
+            }
+        }
+    }
+}
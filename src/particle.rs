@@ -1,11 +1,19 @@
 //! Has things relating to particles, most notably the `Particle` struct and `ParticleQueue`
 
 use crate::vec::Vec3;
+use std::collections::HashMap;
+
+/// Every collision channel, particles and colliders collide on all of them by default
+pub const ALL_CHANNELS: i32 = 0xff;
+/// The highest particle group, FleX only has 20 bits for it in a particle's phase
+pub const MAX_GROUP: i32 = (1 << 20) - 1;
 
 /// A particle is a blueprint for a FleX particle, usually in a `ParticleQueue`
 pub struct Particle {
     pub pos: Vec3,
     pub vel: Vec3,
+    /// The particle group, which decides the collision channels of the particle
+    pub group: i32,
 }
 
 /// A queue of particles, used to create FleX particles
@@ -15,6 +23,10 @@ pub struct ParticleQueue {
     pub particleCount: i32,
     /// Used for queuing up a `Particle` to be added to the solvers
     pub particles: Vec<Particle>,
+    /// The collision channel mask of each particle group, groups not in here use `ALL_CHANNELS`
+    pub groupChannels: HashMap<i32, i32>,
+    /// Set when a group changed channels, so the solver has to rewrite the phases of existing particles
+    pub channelsChanged: bool,
 }
 
 impl ParticleQueue {
@@ -23,9 +35,22 @@ impl ParticleQueue {
         Self {
             particles: Vec::new(),
             particleCount: 0,
+            groupChannels: HashMap::new(),
+            channelsChanged: false,
         }
     }
 
+    /// Gets the collision channel mask of a particle group
+    pub fn channels(&self, group: i32) -> i32 {
+        *self.groupChannels.get(&group).unwrap_or(&ALL_CHANNELS)
+    }
+
+    /// Sets the collision channel mask of a particle group, this also applies to particles that already exist
+    pub fn setChannels(&mut self, group: i32, channels: i32) {
+        self.groupChannels.insert(group, channels & ALL_CHANNELS);
+        self.channelsChanged = true;
+    }
+
     /// Adds a particle to the queue
     pub fn add_particle(&mut self, particle: Particle) {
         self.particles.push(particle);
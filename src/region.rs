@@ -0,0 +1,97 @@
+//! Volumes of space that things acting on particles are limited to
+
+use crate::vec::Vec3;
+
+#[derive(Clone, Debug)]
+pub enum Region {
+    Sphere {
+        center: Vec3,
+        radius: f32,
+    },
+    /// An axis aligned box
+    Box {
+        lower: Vec3,
+        upper: Vec3,
+    },
+    /// Every point within `radius` of the line from `start` to `end`
+    Capsule {
+        start: Vec3,
+        end: Vec3,
+        radius: f32,
+    },
+}
+
+impl Region {
+    /// Checks if a point is inside the region
+    pub fn contains(&self, point: &Vec3) -> bool {
+        match self {
+            Region::Box { lower, upper } => {
+                point.x >= lower.x
+                    && point.y >= lower.y
+                    && point.z >= lower.z
+                    && point.x <= upper.x
+                    && point.y <= upper.y
+                    && point.z <= upper.z
+            }
+            _ => self.depth(point).is_some(),
+        }
+    }
+
+    /// How far a point is from the core of the region, going from 0 (at the center, or on the line of a capsule)
+    /// to 1 (on the surface), `None` if it's outside
+    pub fn depth(&self, point: &Vec3) -> Option<f32> {
+        let depth = match self {
+            Region::Sphere { center, radius } => {
+                Vec3::sub(point, center).length() / radius.max(f32::EPSILON)
+            }
+            Region::Box { lower, upper } => {
+                let center = Vec3::lerp(lower, upper, 0.5);
+                let axis = |value: f32, center: f32, lower: f32| {
+                    let half = center - lower;
+                    if half > f32::EPSILON {
+                        (value - center).abs() / half
+                    } else if (value - center).abs() <= f32::EPSILON {
+                        0.0
+                    } else {
+                        f32::INFINITY
+                    }
+                };
+
+                axis(point.x, center.x, lower.x)
+                    .max(axis(point.y, center.y, lower.y))
+                    .max(axis(point.z, center.z, lower.z))
+            }
+            Region::Capsule { start, end, radius } => {
+                Vec3::sub(point, &closestOnSegment(start, end, point)).length()
+                    / radius.max(f32::EPSILON)
+            }
+        };
+
+        if depth <= 1.0 {
+            Some(depth)
+        } else {
+            None
+        }
+    }
+
+    /// The middle of the region
+    pub fn center(&self) -> Vec3 {
+        match self {
+            Region::Sphere { center, .. } => center.clone(),
+            Region::Box { lower, upper } => Vec3::lerp(lower, upper, 0.5),
+            Region::Capsule { start, end, .. } => Vec3::lerp(start, end, 0.5),
+        }
+    }
+}
+
+/// Finds the point on the line from `start` to `end` which is closest to `point`
+pub fn closestOnSegment(start: &Vec3, end: &Vec3, point: &Vec3) -> Vec3 {
+    let line = Vec3::sub(end, start);
+    let lengthSquared = Vec3::dot(&line, &line);
+    if lengthSquared <= f32::EPSILON {
+        return start.clone();
+    }
+
+    let fraction = Vec3::dot(&Vec3::sub(point, start), &line) / lengthSquared;
+    Vec3::lerp(start, end, fraction.clamp(0.0, 1.0))
+}
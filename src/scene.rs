@@ -1,71 +1,368 @@
 //! A `Scene` describes every object that the particle system will be able to interact with
 
-use crate::collider::Collider;
-use rand::Rng;
+use crate::{
+    collider::Collider,
+    motion::Motion,
+    slotmap::{Handle, SlotMap},
+    vec::{Quat, Vec3},
+};
+use std::fmt;
+
+/// The default hard limit on the number of colliders in a scene
+pub const DEFAULT_COLLIDER_LIMIT: usize = 8192;
+
+/// Attaches a collider to another one, the collider then follows its parent around
+#[derive(Clone)]
+pub struct Attachment {
+    pub parent: Handle,
+    /// The position of the collider, relative to its parent
+    pub offset: Vec3,
+    /// The rotation of the collider, relative to its parent
+    pub rotation: Quat,
+}
 
 pub struct SceneRecord {
-    pub id: i32,
     pub collider: Box<dyn Collider>,
+    /// The collider this one is attached to, if any
+    pub attachment: Option<Attachment>,
+    /// Moves the collider every tick without any help from Lua
+    pub motion: Option<Motion>,
+
+    /// The geometry buffer slot the collider's geometry was last written to, `None` if it never was
+    pub slot: Option<usize>,
+    /// Forces the geometry to be re-initialized on the next tick
+    pub dirty: bool,
+}
+
+impl SceneRecord {
+    /// Checks if the geometry has to be (re-)initialized before the collider can use the slot
+    pub fn needsGeometry(&self, slot: usize) -> bool {
+        self.dirty || self.slot != Some(slot) || !self.collider.isInitialized()
+    }
+
+    /// Records that the geometry was written to the slot
+    pub fn assignSlot(&mut self, slot: usize) {
+        self.slot = Some(slot);
+        self.dirty = false;
+    }
+}
+
+/// Errors coming from `Scene` operations, these are raised as Lua errors
+#[derive(Debug)]
+pub enum SceneError {
+    /// The number isn't a collider ID at all
+    InvalidId(f64),
+    /// The collider was removed, the ID will never be valid again
+    StaleId(u64),
+    /// The collider exists, but the operation needs a different kind of collider
+    WrongType(u64, &'static str),
+    /// The scene already holds as many colliders as it is allowed to
+    Full(usize),
+    /// Adding this many colliders at once would go over the limit
+    NoRoom(usize, usize),
+    /// A packed transform batch isn't a whole number of records
+    MalformedBatch(usize),
+    /// Parenting the first collider to the second would make it its own ancestor
+    Cycle(u64, u64),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::InvalidId(id) => write!(f, "{} is not a valid collider ID", id),
+            SceneError::StaleId(id) => {
+                write!(f, "Collider {} does not exist (was it removed?)", id)
+            }
+            SceneError::WrongType(id, expected) => {
+                write!(f, "Collider {} is not a {} collider", id, expected)
+            }
+            SceneError::Full(limit) => write!(f, "The scene is full ({} colliders)", limit),
+            SceneError::NoRoom(count, limit) => write!(
+                f,
+                "{} more colliders do not fit in the scene (limit {}), raise it with Juice.SetColliderLimit",
+                count, limit
+            ),
+            SceneError::MalformedBatch(len) => write!(
+                f,
+                "Packed transforms must be a multiple of {} bytes, got {} bytes",
+                TransformUpdate::PACKED_SIZE,
+                len
+            ),
+            SceneError::Cycle(child, parent) => write!(
+                f,
+                "Collider {} can not be parented to {}, it would become its own parent",
+                child, parent
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+/// A new transform for a collider, part of a batch applied with `Scene::applyTransforms`
+pub struct TransformUpdate {
+    pub id: Handle,
+    /// Left alone when `None`
+    pub position: Option<Vec3>,
+    /// Left alone when `None`
+    pub rotation: Option<Quat>,
 }
+
+impl TransformUpdate {
+    /// The size of a single packed record: f64 ID, 3 f32 position, 4 f32 quaternion (x, y, z, w), all little-endian
+    pub const PACKED_SIZE: usize = 8 + 3 * 4 + 4 * 4;
+
+    /// Decodes a string of packed records, see `PACKED_SIZE` for the layout
+    pub fn decodePacked(bytes: &[u8]) -> Result<Vec<Self>, SceneError> {
+        if bytes.len() % Self::PACKED_SIZE != 0 {
+            return Err(SceneError::MalformedBatch(bytes.len()));
+        }
+
+        let float =
+            |record: &[u8], at: usize| f32::from_le_bytes(record[at..at + 4].try_into().unwrap());
+
+        bytes
+            .chunks_exact(Self::PACKED_SIZE)
+            .map(|record| {
+                let raw = f64::from_le_bytes(record[0..8].try_into().unwrap());
+                let id = Handle::from_number(raw).ok_or(SceneError::InvalidId(raw))?;
+
+                Ok(Self {
+                    id,
+                    position: Some(Vec3::components(
+                        float(record, 8),
+                        float(record, 12),
+                        float(record, 16),
+                    )),
+                    rotation: Some(Quat::components(
+                        float(record, 20),
+                        float(record, 24),
+                        float(record, 28),
+                        float(record, 32),
+                    )),
+                })
+            })
+            .collect()
+    }
+}
+
 pub struct Scene {
-    pub objects: Vec<SceneRecord>,
+    pub objects: SlotMap<SceneRecord>,
+    /// The hard limit on the number of colliders, `add` fails once it is reached
+    pub limit: usize,
 }
 
 impl Scene {
     /// Creates a new scene
     pub fn new() -> Self {
         Self {
-            objects: Vec::new(),
+            objects: SlotMap::new(),
+            limit: DEFAULT_COLLIDER_LIMIT,
         }
     }
 
     /// Checks if an ID is valid
-    pub fn isValid(&self, index: i32) -> bool {
-        for collider in &self.objects {
-            if collider.id == index {
-                return true;
+    pub fn isValid(&self, id: Handle) -> bool {
+        self.objects.contains(id)
+    }
+
+    /// Adds a new collider to the scene, returns its ID
+    pub fn add(&mut self, collider: Box<dyn Collider>) -> Result<Handle, SceneError> {
+        if self.objects.len() >= self.limit {
+            return Err(SceneError::Full(self.limit));
+        }
+
+        Ok(self.objects.insert(SceneRecord {
+            collider,
+            attachment: None,
+            motion: None,
+            slot: None,
+            dirty: true,
+        }))
+    }
+
+    /// Gets a collider from the scene
+    pub fn get(&mut self, id: Handle) -> Result<&mut Box<dyn Collider>, SceneError> {
+        self.objects
+            .get_mut(id)
+            .map(|record| &mut record.collider)
+            .ok_or(SceneError::StaleId(id.to_raw()))
+    }
+
+    /// Removes a collider from the scene
+    pub fn remove(&mut self, id: Handle) -> Result<(), SceneError> {
+        let slot = self
+            .objects
+            .dense_index(id)
+            .ok_or(SceneError::StaleId(id.to_raw()))?;
+
+        self.objects.remove(id);
+
+        // The last record was moved into the removed slot, so its geometry has to be rewritten there
+        if let Some(moved) = self.objects.values_mut().get_mut(slot) {
+            moved.dirty = true;
+        }
+
+        // Children of the removed collider stay where they are, they just stop following it
+        for record in self.objects.values_mut() {
+            if matches!(&record.attachment, Some(attachment) if attachment.parent == id) {
+                record.attachment = None;
             }
         }
 
-        false
+        Ok(())
     }
 
-    /// Adds a new collider to the scene, returns a index
-    pub fn add(&mut self, collider: Box<dyn Collider>) -> i32 {
-        let mut rng = rand::thread_rng();
-        let generatedID = rng.gen_range(1..std::i32::MAX);
+    /// Applies a whole batch of transforms at once
+    /// Every ID is checked first, so a stale ID means none of the batch is applied
+    pub fn applyTransforms(&mut self, updates: Vec<TransformUpdate>) -> Result<(), SceneError> {
+        if let Some(stale) = updates.iter().find(|update| !self.isValid(update.id)) {
+            return Err(SceneError::StaleId(stale.id.to_raw()));
+        }
+
+        for update in updates {
+            let collider = self.get(update.id)?;
 
-        let record = SceneRecord {
-            id: generatedID.clone(),
-            collider: collider,
-        };
+            if let Some(position) = update.position {
+                collider.setPosition(position);
+            }
 
-        self.objects.push(record);
-        generatedID
+            if let Some(rotation) = update.rotation {
+                collider.setRotation(rotation);
+            }
+        }
+
+        Ok(())
     }
 
-    /// Gets a collider from the scene
-    pub fn get(&mut self, colliderIndex: i32) -> Option<&mut Box<dyn Collider>> {
-        if self.isValid(colliderIndex) {
-            for record in self.objects.iter_mut() {
-                if record.id == colliderIndex {
-                    return Some(&mut record.collider);
+    /// Marks a collider's geometry as out of date, it'll be re-initialized on the next tick
+    pub fn markDirty(&mut self, id: Handle) -> Result<(), SceneError> {
+        let record = self
+            .objects
+            .get_mut(id)
+            .ok_or(SceneError::StaleId(id.to_raw()))?;
+
+        record.dirty = true;
+        Ok(())
+    }
+
+    /// Attaches a collider to a parent (or detaches it with `None`)
+    /// Fails if the parent is the collider itself, or already (indirectly) attached to it
+    pub fn setParent(
+        &mut self,
+        id: Handle,
+        attachment: Option<Attachment>,
+    ) -> Result<(), SceneError> {
+        if !self.isValid(id) {
+            return Err(SceneError::StaleId(id.to_raw()));
+        }
+
+        if let Some(attachment) = &attachment {
+            // Walk up from the new parent, if we find the collider on the way it would end up parented to itself
+            let mut ancestor = Some(attachment.parent);
+            while let Some(current) = ancestor {
+                if current == id {
+                    return Err(SceneError::Cycle(id.to_raw(), attachment.parent.to_raw()));
                 }
+
+                ancestor = self
+                    .objects
+                    .get(current)
+                    .ok_or(SceneError::StaleId(current.to_raw()))?
+                    .attachment
+                    .as_ref()
+                    .map(|attachment| attachment.parent);
             }
+        }
+
+        self.objects.get_mut(id).unwrap().attachment = attachment;
+        Ok(())
+    }
+
+    /// Sets (or stops, with `None`) the motion a collider follows on its own
+    pub fn setMotion(&mut self, id: Handle, motion: Option<Motion>) -> Result<(), SceneError> {
+        let record = self
+            .objects
+            .get_mut(id)
+            .ok_or(SceneError::StaleId(id.to_raw()))?;
 
-            None
-        } else {
-            None
+        // Keyframed colliders start at their first keyframe right away, without sweeping there from where they were
+        if let Some(Motion::Keyframes(track)) = &motion {
+            let (position, rotation) = track.sample();
+            record.collider.setPosition(position);
+            record.collider.setRotation(rotation);
+            record.collider.storePrevious();
         }
+
+        record.motion = motion;
+        Ok(())
     }
 
-    /// Removes a collider from the scene
-    pub fn remove(&mut self, collider: i32) {
-        for (idx, record) in self.objects.iter().enumerate() {
-            if record.id == collider {
-                self.objects.swap_remove(idx);
-                return;
+    /// Advances the motion of every collider by `dt` seconds
+    pub fn advanceMotion(&mut self, dt: f32) {
+        for record in self.objects.values_mut() {
+            if let Some(motion) = &mut record.motion {
+                let (position, rotation) = motion.step(
+                    dt,
+                    &record.collider.position(),
+                    &record.collider.state().rotation,
+                );
+
+                record.collider.setPosition(position);
+                record.collider.setRotation(rotation);
+            }
+        }
+    }
+
+    /// Moves every attached collider to where its parent is, parents are always resolved before their children
+    pub fn resolveHierarchy(&mut self) {
+        let count = self.objects.len();
+        let mut visited = vec![false; count];
+        let mut order: Vec<usize> = Vec::with_capacity(count);
+
+        for start in 0..count {
+            // Collect the chain of not-yet-ordered ancestors, the furthest one ends up last
+            let mut chain: Vec<usize> = Vec::new();
+            let mut current = Some(start);
+
+            while let Some(dense) = current {
+                if visited[dense] {
+                    break;
+                }
+
+                visited[dense] = true;
+                chain.push(dense);
+
+                current = self.objects.values()[dense]
+                    .attachment
+                    .as_ref()
+                    .and_then(|attachment| self.objects.dense_index(attachment.parent));
             }
+
+            order.extend(chain.into_iter().rev());
+        }
+
+        for dense in order {
+            let attachment = match &self.objects.values()[dense].attachment {
+                Some(attachment) => attachment.clone(),
+                None => continue,
+            };
+
+            let parent = match self.objects.get(attachment.parent) {
+                Some(parent) => parent.collider.state(),
+                None => continue,
+            };
+
+            let parentRotation = parent.rotation.normalized();
+            let position = Vec3::add(
+                &parent.position,
+                &Quat::rotate(&parentRotation, &attachment.offset),
+            );
+            let rotation = Quat::quat_mul(&parentRotation, &attachment.rotation.normalized());
+
+            let child = &mut self.objects.values_mut()[dense].collider;
+            child.setPosition(position);
+            child.setRotation(rotation);
         }
     }
 
@@ -73,6 +370,16 @@ impl Scene {
     pub fn len(&self) -> usize {
         self.objects.len()
     }
+
+    /// Returns the number of FleX shapes the enabled colliders take up
+    pub fn shapeCount(&self) -> usize {
+        self.objects
+            .values()
+            .iter()
+            .filter(|record| record.collider.isEnabled())
+            .map(|record| record.collider.shapeCount())
+            .sum()
+    }
 }
 
 unsafe impl Send for Scene {}
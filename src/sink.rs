@@ -0,0 +1,187 @@
+//! Sinks remove the particles which end up inside them every tick, counting how many they swallowed
+//!
+//! Sinks live in a `SinkSet` next to the `Scene`, and are handed to Lua by handle just like colliders.
+
+use crate::{
+    region::Region,
+    scene::Scene,
+    slotmap::{Handle, SlotMap},
+    vec::{Quat, Vec3, Vec4},
+};
+use std::fmt;
+
+/// How far outside of a collider particles still count as touching it, particles rest on colliders rather than in them
+pub const DEFAULT_MARGIN: f32 = 12.0;
+
+/// Errors coming from `SinkSet` operations, these are raised as Lua errors
+#[derive(Debug)]
+pub enum SinkError {
+    /// The number isn't a sink ID at all
+    InvalidId(f64),
+    /// The sink was removed, the ID will never be valid again
+    StaleId(u64),
+}
+
+impl fmt::Display for SinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SinkError::InvalidId(id) => write!(f, "{} is not a valid sink ID", id),
+            SinkError::StaleId(id) => write!(f, "Sink {} does not exist (was it removed?)", id),
+        }
+    }
+}
+
+impl std::error::Error for SinkError {}
+
+/// Where a sink swallows particles
+#[derive(Clone, Debug)]
+pub enum SinkVolume {
+    Region(Region),
+    /// Anywhere within `margin` of the (rotated) bounds of a collider
+    Collider {
+        id: Handle,
+        margin: f32,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct Sink {
+    pub volume: SinkVolume,
+    pub enabled: bool,
+    /// How many particles the sink swallowed so far
+    pub absorbed: u64,
+}
+
+impl Sink {
+    /// Creates an enabled sink which hasn't swallowed anything yet
+    pub fn new(volume: SinkVolume) -> Self {
+        Self {
+            volume,
+            enabled: true,
+            absorbed: 0,
+        }
+    }
+}
+
+/// A sink volume resolved for this tick, so colliders are only looked up once
+enum Test<'a> {
+    Region(&'a Region),
+    /// A box in the local space of a collider
+    Oriented {
+        position: Vec3,
+        inverse: Quat,
+        lower: Vec3,
+        upper: Vec3,
+    },
+}
+
+impl<'a> Test<'a> {
+    fn contains(&self, point: &Vec3) -> bool {
+        match self {
+            Test::Region(region) => region.contains(point),
+            Test::Oriented {
+                position,
+                inverse,
+                lower,
+                upper,
+            } => {
+                let local = Quat::rotate(inverse, &Vec3::sub(point, position));
+                local.x >= lower.x
+                    && local.y >= lower.y
+                    && local.z >= lower.z
+                    && local.x <= upper.x
+                    && local.y <= upper.y
+                    && local.z <= upper.z
+            }
+        }
+    }
+}
+
+/// Every sink, the solver lets them swallow particles each tick
+pub struct SinkSet {
+    pub sinks: SlotMap<Sink>,
+}
+
+impl SinkSet {
+    /// Creates a new, empty sink set
+    pub fn new() -> Self {
+        Self {
+            sinks: SlotMap::new(),
+        }
+    }
+
+    /// Adds a sink, returning its handle
+    pub fn add(&mut self, sink: Sink) -> Handle {
+        self.sinks.insert(sink)
+    }
+
+    /// Gets a sink by handle
+    pub fn get(&mut self, id: Handle) -> Result<&mut Sink, SinkError> {
+        self.sinks
+            .get_mut(id)
+            .ok_or(SinkError::StaleId(id.to_raw()))
+    }
+
+    /// Removes a sink by handle
+    pub fn remove(&mut self, id: Handle) -> Result<(), SinkError> {
+        self.sinks
+            .remove(id)
+            .map(|_| ())
+            .ok_or(SinkError::StaleId(id.to_raw()))
+    }
+
+    /// Finds the particles inside any enabled sink, returning their (sorted) indices **(INTERNAL)**
+    /// A particle inside several sinks only counts towards the first one
+    pub fn absorb(&mut self, positions: &[Vec4], scene: &Scene) -> Vec<usize> {
+        let mut absorbed = Vec::new();
+
+        // Sinks on colliders which were removed swallow nothing
+        let mut tests: Vec<(usize, Test)> = Vec::new();
+        for (index, sink) in self.sinks.values().iter().enumerate() {
+            if !sink.enabled {
+                continue;
+            }
+
+            match &sink.volume {
+                SinkVolume::Region(region) => tests.push((index, Test::Region(region))),
+                SinkVolume::Collider { id, margin } => {
+                    if let Some(record) = scene.objects.get(*id) {
+                        // The bounds go with the rotation as it was set, without any alignment for FleX on top,
+                        // and the conjugate is only the inverse of a unit quaternion
+                        let (lower, upper) = record.collider.bounds();
+                        let margin = Vec3::components(*margin, *margin, *margin);
+
+                        tests.push((
+                            index,
+                            Test::Oriented {
+                                position: record.collider.position(),
+                                inverse: record.collider.state().rotation.normalized().conjugate(),
+                                lower: Vec3::sub(&lower, &margin),
+                                upper: Vec3::add(&upper, &margin),
+                            },
+                        ));
+                    }
+                }
+            }
+        }
+
+        if tests.is_empty() {
+            return absorbed;
+        }
+
+        let mut counts = vec![0u64; self.sinks.len()];
+        for (particle, position) in positions.iter().enumerate() {
+            let point = position.xyz();
+            if let Some((index, _)) = tests.iter().find(|(_, test)| test.contains(&point)) {
+                counts[*index] += 1;
+                absorbed.push(particle);
+            }
+        }
+
+        for (sink, count) in self.sinks.values_mut().iter_mut().zip(counts) {
+            sink.absorbed += count;
+        }
+
+        absorbed
+    }
+}
@@ -0,0 +1,205 @@
+//! A generational slot map, hands out `Handle`s that can never collide and are never reused while stale
+//!
+//! # Layout
+//! Values are stored densely (so they can be iterated and written to FleX buffers in order), and each `Handle` points
+//! at a sparse slot which knows where its value currently lives. Removing a value swaps the last value into its place.
+
+/// Generations are packed into the upper bits of a Lua number, which can only exactly hold 53 bits
+const GENERATION_BITS: u32 = 21;
+const MAX_GENERATION: u32 = (1 << GENERATION_BITS) - 1;
+
+/// A reference to a value in a `SlotMap`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Handle {
+    index: u32,
+    generation: u32,
+}
+
+impl Handle {
+    /// Packs the handle into a single number, which is exactly representable as a Lua number
+    pub fn to_raw(self) -> u64 {
+        ((self.generation as u64) << 32) | self.index as u64
+    }
+
+    /// Unpacks a handle from a number created by `to_raw`
+    pub fn from_raw(raw: u64) -> Option<Self> {
+        let generation = (raw >> 32) as u32;
+        if generation == 0 || generation > MAX_GENERATION {
+            return None;
+        }
+
+        Some(Self {
+            index: raw as u32,
+            generation,
+        })
+    }
+
+    /// Unpacks a handle from a Lua number, which has to be a whole number created by `to_raw`
+    pub fn from_number(number: f64) -> Option<Self> {
+        // Lua numbers are doubles, so anything fractional, negative or beyond 2^53 can't be one of ours
+        if !(0.0..9007199254740992.0).contains(&number) || number.fract() != 0.0 {
+            return None;
+        }
+
+        Self::from_raw(number as u64)
+    }
+}
+
+struct Slot {
+    generation: u32,
+    /// Where the value lives in the dense storage, `None` when the slot is free
+    dense: Option<usize>,
+}
+
+pub struct SlotMap<T> {
+    slots: Vec<Slot>,
+    free: Vec<u32>,
+
+    values: Vec<T>,
+    /// The handle of each value in `values`
+    handles: Vec<Handle>,
+}
+
+impl<T> SlotMap<T> {
+    /// Instantiates a new, empty slot map
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+            values: Vec::new(),
+            handles: Vec::new(),
+        }
+    }
+
+    /// Inserts a value, returning the handle which refers to it
+    pub fn insert(&mut self, value: T) -> Handle {
+        let dense = self.values.len();
+
+        let handle = match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index as usize];
+                slot.dense = Some(dense);
+
+                Handle {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 1,
+                    dense: Some(dense),
+                });
+
+                Handle {
+                    index: (self.slots.len() - 1) as u32,
+                    generation: 1,
+                }
+            }
+        };
+
+        self.values.push(value);
+        self.handles.push(handle);
+        handle
+    }
+
+    /// Gets the position of a value in the dense storage
+    pub fn dense_index(&self, handle: Handle) -> Option<usize> {
+        let slot = self.slots.get(handle.index as usize)?;
+        if slot.generation != handle.generation {
+            return None;
+        }
+
+        slot.dense
+    }
+
+    /// Checks if the handle still refers to a value
+    pub fn contains(&self, handle: Handle) -> bool {
+        self.dense_index(handle).is_some()
+    }
+
+    /// Gets a value
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        let dense = self.dense_index(handle)?;
+        self.values.get(dense)
+    }
+
+    /// Gets a value mutably
+    pub fn get_mut(&mut self, handle: Handle) -> Option<&mut T> {
+        let dense = self.dense_index(handle)?;
+        self.values.get_mut(dense)
+    }
+
+    /// Removes a value, the last value is moved into its place in the dense storage
+    pub fn remove(&mut self, handle: Handle) -> Option<T> {
+        let dense = self.dense_index(handle)?;
+
+        let slot = &mut self.slots[handle.index as usize];
+        slot.dense = None;
+        slot.generation += 1;
+
+        // Slots which ran out of generations are retired, so a stale handle can never become valid again
+        if slot.generation <= MAX_GENERATION {
+            self.free.push(handle.index);
+        }
+
+        let value = self.values.swap_remove(dense);
+        self.handles.swap_remove(dense);
+
+        // Point the moved value's slot at its new home
+        if let Some(moved) = self.handles.get(dense) {
+            self.slots[moved.index as usize].dense = Some(dense);
+        }
+
+        Some(value)
+    }
+
+    /// Returns the number of values
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Every value, in dense order
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+
+    /// Every value mutably, in dense order
+    pub fn values_mut(&mut self) -> &mut [T] {
+        &mut self.values
+    }
+
+    /// Every handle, in the same order as `values`
+    pub fn handles(&self) -> &[Handle] {
+        &self.handles
+    }
+
+    /// Iterates every handle along with its value
+    pub fn iter(&self) -> impl Iterator<Item = (Handle, &T)> {
+        self.handles.iter().copied().zip(self.values.iter())
+    }
+
+    /// Iterates every handle along with its value, mutably
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Handle, &mut T)> {
+        self.handles.iter().copied().zip(self.values.iter_mut())
+    }
+
+    /// Removes every value, previously handed out handles stay stale
+    pub fn clear(&mut self) {
+        for handle in self.handles.drain(..) {
+            let slot = &mut self.slots[handle.index as usize];
+            slot.dense = None;
+            slot.generation += 1;
+
+            if slot.generation <= MAX_GENERATION {
+                self.free.push(handle.index);
+            }
+        }
+
+        self.values.clear();
+    }
+}
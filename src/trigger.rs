@@ -0,0 +1,226 @@
+//! Triggers count the particles inside them every tick, and note when that count crosses their thresholds
+//!
+//! Triggers live in a `TriggerSet` next to the `Scene`, and are handed to Lua by handle just like colliders.
+//! Notifications pile up until Lua polls them, so nothing is called back from the solver thread.
+
+use crate::{
+    region::Region,
+    slotmap::{Handle, SlotMap},
+    vec::{Vec3, Vec4},
+};
+use std::{collections::VecDeque, fmt};
+
+/// The most notifications kept around for Lua, the oldest are dropped first
+pub const MAX_NOTIFICATIONS: usize = 1024;
+
+/// Errors coming from `TriggerSet` operations, these are raised as Lua errors
+#[derive(Debug)]
+pub enum TriggerError {
+    /// The number isn't a trigger ID at all
+    InvalidId(f64),
+    /// The trigger was removed, the ID will never be valid again
+    StaleId(u64),
+}
+
+impl fmt::Display for TriggerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TriggerError::InvalidId(id) => write!(f, "{} is not a valid trigger ID", id),
+            TriggerError::StaleId(id) => {
+                write!(f, "Trigger {} does not exist (was it removed?)", id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TriggerError {}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TriggerEvent {
+    /// The first particle got into an empty trigger
+    Enter,
+    /// The last particle left the trigger
+    Exit,
+    /// The count reached the fill threshold
+    Filled,
+    /// The count dropped to the empty threshold, after the trigger was filled
+    Emptied,
+}
+
+impl TriggerEvent {
+    /// The name Lua sees
+    pub fn name(self) -> &'static str {
+        match self {
+            TriggerEvent::Enter => "enter",
+            TriggerEvent::Exit => "exit",
+            TriggerEvent::Filled => "filled",
+            TriggerEvent::Emptied => "emptied",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Notification {
+    pub trigger: Handle,
+    pub event: TriggerEvent,
+    /// The tick the threshold was crossed on
+    pub tick: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Trigger {
+    pub region: Region,
+    /// How many particles make the trigger filled, `None` never fills it
+    pub fill: Option<u32>,
+    /// How few particles make a filled trigger empty again
+    pub empty: u32,
+    pub enabled: bool,
+
+    /// How many particles were inside on the last tick
+    pub count: u32,
+    /// The mean velocity of the particles inside on the last tick
+    pub meanVelocity: Vec3,
+    /// Whether the fill threshold was reached, and the trigger didn't empty since
+    pub filled: bool,
+}
+
+impl Trigger {
+    /// Creates an enabled, empty trigger
+    pub fn new(region: Region, fill: Option<u32>, empty: u32) -> Self {
+        Self {
+            region,
+            fill,
+            empty,
+            enabled: true,
+            count: 0,
+            meanVelocity: Vec3::new(),
+            filled: false,
+        }
+    }
+
+    /// Changes the thresholds, a trigger which can't fill anymore isn't filled either
+    pub fn setThresholds(&mut self, fill: Option<u32>, empty: u32) {
+        self.fill = fill;
+        self.empty = empty;
+
+        if fill.is_none() {
+            self.filled = false;
+        }
+    }
+
+    /// Turns the trigger on or off, a disabled trigger counts nothing so it starts over empty
+    pub fn setEnabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+
+        if !enabled {
+            self.count = 0;
+            self.meanVelocity = Vec3::new();
+            self.filled = false;
+        }
+    }
+
+    /// Counts the particles inside, returning the thresholds crossed since the last count
+    fn update(&mut self, positions: &[Vec4], velocities: &[Vec3]) -> Vec<TriggerEvent> {
+        let mut count = 0u32;
+        let mut total = Vec3::new();
+
+        for (particle, velocity) in positions.iter().zip(velocities) {
+            if self.region.contains(&particle.xyz()) {
+                count += 1;
+                total = Vec3::add(&total, velocity);
+            }
+        }
+
+        let mut events = Vec::new();
+        if self.count == 0 && count > 0 {
+            events.push(TriggerEvent::Enter);
+        }
+        if self.count > 0 && count == 0 {
+            events.push(TriggerEvent::Exit);
+        }
+
+        if let Some(fill) = self.fill {
+            if !self.filled && count >= fill {
+                self.filled = true;
+                events.push(TriggerEvent::Filled);
+            } else if self.filled && count <= self.empty {
+                self.filled = false;
+                events.push(TriggerEvent::Emptied);
+            }
+        }
+
+        self.count = count;
+        self.meanVelocity = if count > 0 {
+            total.scale(1.0 / count as f32)
+        } else {
+            Vec3::new()
+        };
+
+        events
+    }
+}
+
+/// Every trigger, along with the notifications Lua hasn't polled yet
+pub struct TriggerSet {
+    pub triggers: SlotMap<Trigger>,
+    pub notifications: VecDeque<Notification>,
+}
+
+impl TriggerSet {
+    /// Creates a new, empty trigger set
+    pub fn new() -> Self {
+        Self {
+            triggers: SlotMap::new(),
+            notifications: VecDeque::new(),
+        }
+    }
+
+    /// Adds a trigger, returning its handle
+    pub fn add(&mut self, trigger: Trigger) -> Handle {
+        self.triggers.insert(trigger)
+    }
+
+    /// Gets a trigger by handle
+    pub fn get(&mut self, id: Handle) -> Result<&mut Trigger, TriggerError> {
+        self.triggers
+            .get_mut(id)
+            .ok_or(TriggerError::StaleId(id.to_raw()))
+    }
+
+    /// Removes a trigger by handle, its notifications which weren't polled yet go with it
+    pub fn remove(&mut self, id: Handle) -> Result<(), TriggerError> {
+        self.triggers
+            .remove(id)
+            .ok_or(TriggerError::StaleId(id.to_raw()))?;
+        self.notifications
+            .retain(|notification| notification.trigger != id);
+
+        Ok(())
+    }
+
+    /// Hands over every notification, oldest first
+    pub fn poll(&mut self) -> Vec<Notification> {
+        self.notifications.drain(..).collect()
+    }
+
+    /// Recounts every enabled trigger **(INTERNAL)**
+    pub fn update(&mut self, positions: &[Vec4], velocities: &[Vec3], tick: u64) {
+        for (id, trigger) in self.triggers.iter_mut() {
+            if !trigger.enabled {
+                continue;
+            }
+
+            for event in trigger.update(positions, velocities) {
+                if self.notifications.len() >= MAX_NOTIFICATIONS {
+                    self.notifications.pop_front();
+                }
+
+                self.notifications.push_back(Notification {
+                    trigger: id,
+                    event,
+                    tick,
+                });
+            }
+        }
+    }
+}
@@ -49,6 +49,143 @@ impl Vec4 {
             w: 1.0 / 2.0,
         }
     }
+
+    /// Drops the `w` component, turning a particle into its position
+    pub fn xyz(&self) -> Vec3 {
+        Vec3::components(self.x, self.y, self.z)
+    }
+
+    /// A quaternion which doesn't rotate anything
+    pub fn identity() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            w: 1.0,
+        }
+    }
+
+    /// Converts Source engine Euler angles (pitch, yaw, roll, in degrees) into a quaternion
+    /// This mirrors `AngleQuaternion` from the Source SDK, so it matches `Angle` in Lua exactly
+    pub fn from_angles(pitch: f32, yaw: f32, roll: f32) -> Quat {
+        let (sy, cy) = (yaw.to_radians() * 0.5).sin_cos();
+        let (sp, cp) = (pitch.to_radians() * 0.5).sin_cos();
+        let (sr, cr) = (roll.to_radians() * 0.5).sin_cos();
+
+        let srXcp = sr * cp;
+        let crXsp = cr * sp;
+        let crXcp = cr * cp;
+        let srXsp = sr * sp;
+
+        Self {
+            x: srXcp * cy - crXsp * sy,
+            y: crXsp * cy + srXcp * sy,
+            z: crXcp * sy - srXsp * cy,
+            w: crXcp * cy + srXsp * sy,
+        }
+    }
+
+    /// Multiplies two quaternions, the result rotates by `other` first, then by `left`
+    pub fn quat_mul(left: &Quat, other: &Quat) -> Quat {
+        Self {
+            x: left.w * other.x + left.x * other.w + left.y * other.z - left.z * other.y,
+            y: left.w * other.y - left.x * other.z + left.y * other.w + left.z * other.x,
+            z: left.w * other.z + left.x * other.y - left.y * other.x + left.z * other.w,
+            w: left.w * other.w - left.x * other.x - left.y * other.y - left.z * other.z,
+        }
+    }
+
+    /// Rotates a vector by a (unit) quaternion
+    pub fn rotate(quat: &Quat, v: &Vec3) -> Vec3 {
+        // t = 2 * cross(q.xyz, v)
+        let tx = 2.0 * (quat.y * v.z - quat.z * v.y);
+        let ty = 2.0 * (quat.z * v.x - quat.x * v.z);
+        let tz = 2.0 * (quat.x * v.y - quat.y * v.x);
+
+        // v' = v + w * t + cross(q.xyz, t)
+        Vec3::components(
+            v.x + quat.w * tx + (quat.y * tz - quat.z * ty),
+            v.y + quat.w * ty + (quat.z * tx - quat.x * tz),
+            v.z + quat.w * tz + (quat.x * ty - quat.y * tx),
+        )
+    }
+
+    /// The opposite rotation of a (unit) quaternion
+    pub fn conjugate(&self) -> Quat {
+        Self {
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+            w: self.w,
+        }
+    }
+
+    /// A quaternion rotating `radians` around `axis`, the axis doesn't have to be unit length
+    pub fn from_axis_angle(axis: &Vec3, radians: f32) -> Quat {
+        let length = axis.length();
+        if length <= f32::EPSILON {
+            return Self::identity();
+        }
+
+        let (sin, cos) = (radians * 0.5).sin_cos();
+        let scale = sin / length;
+
+        Self {
+            x: axis.x * scale,
+            y: axis.y * scale,
+            z: axis.z * scale,
+            w: cos,
+        }
+    }
+
+    /// Spherically interpolates between two unit quaternions, always taking the shortest way around
+    pub fn slerp(from: &Quat, to: &Quat, fraction: f32) -> Quat {
+        let mut dot = from.x * to.x + from.y * to.y + from.z * to.z + from.w * to.w;
+
+        // q and -q are the same rotation, flip one so we don't go the long way around
+        let sign = if dot < 0.0 {
+            dot = -dot;
+            -1.0
+        } else {
+            1.0
+        };
+
+        // Nearly parallel, a linear blend is good enough and avoids dividing by ~0
+        let (fromWeight, toWeight) = if dot > 0.9995 {
+            (1.0 - fraction, fraction)
+        } else {
+            let angle = dot.acos();
+            let sin = angle.sin();
+            (
+                ((1.0 - fraction) * angle).sin() / sin,
+                (fraction * angle).sin() / sin,
+            )
+        };
+        let toWeight = toWeight * sign;
+
+        Self {
+            x: from.x * fromWeight + to.x * toWeight,
+            y: from.y * fromWeight + to.y * toWeight,
+            z: from.z * fromWeight + to.z * toWeight,
+            w: from.w * fromWeight + to.w * toWeight,
+        }
+        .normalized()
+    }
+
+    /// Returns the quaternion scaled to a length of 1, FleX expects unit quaternions for rotations
+    pub fn normalized(&self) -> Quat {
+        let length = (self.x * self.x + self.y * self.y + self.z * self.z + self.w * self.w).sqrt();
+        if length <= f32::EPSILON {
+            return Self::identity();
+        }
+
+        Self {
+            x: self.x / length,
+            y: self.y / length,
+            z: self.z / length,
+            w: self.w / length,
+        }
+    }
 }
 
 impl Vec3 {
@@ -63,6 +200,65 @@ impl Vec3 {
     pub fn components(x: f32, y: f32, z: f32) -> Self {
         Self { x, y, z }
     }
+
+    pub fn add(left: &Vec3, other: &Vec3) -> Self {
+        Self {
+            x: left.x + other.x,
+            y: left.y + other.y,
+            z: left.z + other.z,
+        }
+    }
+
+    pub fn sub(left: &Vec3, other: &Vec3) -> Self {
+        Self {
+            x: left.x - other.x,
+            y: left.y - other.y,
+            z: left.z - other.z,
+        }
+    }
+
+    pub fn dot(left: &Vec3, other: &Vec3) -> f32 {
+        left.x * other.x + left.y * other.y + left.z * other.z
+    }
+
+    pub fn cross(left: &Vec3, other: &Vec3) -> Self {
+        Self {
+            x: left.y * other.z - left.z * other.y,
+            y: left.z * other.x - left.x * other.z,
+            z: left.x * other.y - left.y * other.x,
+        }
+    }
+
+    pub fn scale(&self, factor: f32) -> Self {
+        Self {
+            x: self.x * factor,
+            y: self.y * factor,
+            z: self.z * factor,
+        }
+    }
+
+    pub fn length(&self) -> f32 {
+        (self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
+    }
+
+    /// Returns the vector scaled to a length of 1, or a zero vector if it has no length to scale
+    pub fn normalized(&self) -> Vec3 {
+        let length = self.length();
+        if length <= f32::EPSILON {
+            return Vec3::new();
+        }
+
+        self.scale(1.0 / length)
+    }
+
+    /// Linearly interpolates between two vectors, a fraction of 0 gives `from` and 1 gives `to`
+    pub fn lerp(from: &Vec3, to: &Vec3, fraction: f32) -> Self {
+        Self {
+            x: from.x + (to.x - from.x) * fraction,
+            y: from.y + (to.y - from.y) * fraction,
+            z: from.z + (to.z - from.z) * fraction,
+        }
+    }
 }
 
 pub type Quat = Vec4;